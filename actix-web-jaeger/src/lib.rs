@@ -5,20 +5,82 @@ extern crate jaeger_client_rust;
 extern crate log;
 extern crate opentracing_rust_wip;
 
+use actix_web_opentracing::TracedRequest;
+use jaeger_client_rust::{TraceIds, Tracer as JaegerTracer};
+
+/// The `trace_id=… span_id=…` fields of the span tracing the request, or an
+/// empty string if it isn't traced, for tagging messages logged about it:
+///
+/// ```ignore
+/// info!("{} charging card", request_trace_ids(&req));
+/// ```
+pub fn request_trace_ids<R>(request: &R) -> String
+where
+    R: TracedRequest<JaegerTracer>,
+{
+    request
+        .span_context()
+        .map(|context| TraceIds(&context).to_string())
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use actix_web::dev::{Service, ServiceResponse};
+    use actix_web::error::ErrorInternalServerError;
     use actix_web::{http::StatusCode, test, web, App, HttpResponse};
     use actix_web_opentracing::*;
-    use jaeger_client_rust::Tracer as JaegerTracer;
-    use log::trace;
-    use opentracing_rust_wip::Span;
+    use jaeger_client_rust::{Span as JaegerSpan, SpanLogger};
+    use log::{trace, Level, Log, Record};
+    use opentracing_rust_wip::{Reporter, SpanGuard, TagValue};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    /// The parts of a reported span that the tests look at.
+    struct ReportedSpan {
+        operation_name: String,
+        trace_ids: String,
+        tags: HashMap<String, TagValue>,
+        logs: Vec<HashMap<String, TagValue>>,
+    }
+
+    /// Records every span reported to it.
+    #[derive(Clone, Default)]
+    struct CapturingReporter {
+        spans: Rc<RefCell<Vec<ReportedSpan>>>,
+    }
+
+    impl<'a> Reporter<'a> for CapturingReporter {
+        type Span = JaegerSpan;
+
+        fn report(&self, span: &JaegerSpan) {
+            self.spans.borrow_mut().push(ReportedSpan {
+                operation_name: span.operation_name.clone(),
+                trace_ids: TraceIds(&span.context).to_string(),
+                tags: span.tags.clone(),
+                logs: span.logs.iter().map(|(_, fields)| fields.clone()).collect(),
+            });
+        }
+    }
 
     fn index(req: web::HttpRequest) -> HttpResponse {
         trace!("Handling request {:?}", req);
-        let span = TracedRequest::<JaegerTracer>::start_child_span(&req, "child span".to_owned());
-        let response = HttpResponse::Ok().into();
-        let _finished_span = span.map(|span| span.finish());
-        response
+        let _span = TracedRequest::<JaegerTracer>::start_child_span(&req, "child span".to_owned())
+            .map(SpanGuard::new);
+        HttpResponse::Ok().into()
+    }
+
+    fn logging_index(req: web::HttpRequest) -> HttpResponse {
+        SpanLogger::new().log(
+            &Record::builder()
+                .args(format_args!("handled {}", request_trace_ids(&req)))
+                .level(Level::Info)
+                .target("handler")
+                .build(),
+        );
+        HttpResponse::Ok().into()
     }
 
     #[test]
@@ -46,4 +108,52 @@ mod tests {
         let resp = test::call_service(&mut app, req);
         assert_eq!(resp.status(), StatusCode::OK);
     }
+
+    #[test]
+    fn test_handler_logs_on_request_span() {
+        let reporter = CapturingReporter::default();
+        let request_tracer =
+            HttpRequestTracer::new(JaegerTracer::default().with_reporter(reporter.clone()));
+
+        let mut app = test::init_service(
+            App::new()
+                .wrap(request_tracer)
+                .service(web::resource("/log").to(logging_index)),
+        );
+
+        let req = test::TestRequest::with_uri("/log").to_request();
+        let resp = test::call_service(&mut app, req);
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let spans = reporter.spans.borrow();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].operation_name, "HTTP (GET)");
+        assert_eq!(spans[0].logs.len(), 1);
+        assert_eq!(
+            spans[0].logs[0]["message"],
+            TagValue::String(format!("handled {}", spans[0].trace_ids))
+        );
+    }
+
+    #[test]
+    fn test_failing_service_finishes_span_once() {
+        let reporter = CapturingReporter::default();
+        let request_tracer =
+            HttpRequestTracer::new(JaegerTracer::default().with_reporter(reporter.clone()));
+
+        let mut app = test::init_service(
+            App::new()
+                .wrap_fn(|_req, _srv| Err::<ServiceResponse, _>(ErrorInternalServerError("failed")))
+                .wrap(request_tracer)
+                .service(web::resource("/fail").to(index)),
+        );
+
+        let req = test::TestRequest::with_uri("/fail").to_request();
+        assert!(test::block_on(app.call(req)).is_err());
+
+        let spans = reporter.spans.borrow();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].operation_name, "HTTP (GET)");
+        assert_eq!(spans[0].tags["error"], TagValue::Boolean(true));
+    }
 }
@@ -0,0 +1,64 @@
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::HeaderMap;
+use log::trace;
+use opentracing_rust_wip::{TextMapReader, TextMapWriter};
+
+/// Reads span contexts straight out of an actix `HeaderMap`.
+///
+/// Header names are matched case-insensitively and values that are not
+/// visible ASCII are skipped.
+pub struct HeaderMapReader<'a> {
+    headers: &'a HeaderMap,
+}
+
+impl<'a> HeaderMapReader<'a> {
+    pub fn new(headers: &'a HeaderMap) -> Self {
+        HeaderMapReader { headers }
+    }
+}
+
+impl<'a> TextMapReader for HeaderMapReader<'a> {
+    fn get_first(&self, key: &str) -> Option<&str> {
+        self.headers
+            .get_all(key)
+            .flat_map(|value| value.to_str().ok())
+            .next()
+    }
+
+    fn get_all(&self, key: &str) -> Vec<&str> {
+        self.headers
+            .get_all(key)
+            .flat_map(|value| value.to_str().ok())
+            .collect()
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.headers.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+/// Writes span contexts straight into an actix `HeaderMap`, such as the
+/// headers of an outgoing client request.
+pub struct HeaderMapWriter<'a> {
+    headers: &'a mut HeaderMap,
+}
+
+impl<'a> HeaderMapWriter<'a> {
+    pub fn new(headers: &'a mut HeaderMap) -> Self {
+        HeaderMapWriter { headers }
+    }
+}
+
+impl<'a> TextMapWriter for HeaderMapWriter<'a> {
+    fn set(&mut self, key: &str, value: &str) {
+        match (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                self.headers.insert(name, value);
+            }
+            _ => trace!("Not injecting invalid header {}: {}", key, value),
+        }
+    }
+}
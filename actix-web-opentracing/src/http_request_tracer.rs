@@ -7,7 +7,7 @@ use futures::future::{ok, FutureResult};
 use futures::{Async, Future, Poll};
 use log::trace;
 use opentracing_rust_wip::*;
-use std::collections::HashMap;
+use HeaderMapReader;
 use std::marker::PhantomData;
 use std::rc::*;
 
@@ -23,16 +23,25 @@ impl<T: Tracer<'static>> HttpRequestTracer<T> {
     }
 }
 
+/// Access to the tracer and span the `HttpRequestTracer` middleware attached to
+/// a request.
+///
+/// The middleware finishes the request span itself, so requests only carry its
+/// context. Code that used `span()` to start child spans can call
+/// `start_child_span`, or pass `span_context()` as the parent to the tracer;
+/// `set_span` is replaced by `set_span_context`.
 pub trait TracedRequest<T>
 where
     T: Tracer<'static>,
 {
     fn tracer(&self) -> Option<Rc<T>>;
-    fn parent_span_context(&self) -> Option<T::SpanContext>;
-    fn span(&self) -> Option<Rc<T::Span>>;
+    /// Extracts the span context sent by the caller, if any.
+    fn parent_span_context(&self) -> Result<Option<T::SpanContext>, T::Error>;
+    /// The context of the span tracing this request.
+    fn span_context(&self) -> Option<T::SpanContext>;
 
     fn set_tracer(&self, tracer: Rc<T>);
-    fn set_span(&self, span: T::Span);
+    fn set_span_context(&self, span_context: T::SpanContext);
 }
 
 impl<T> TracedRequest<T>
@@ -41,11 +50,8 @@ where
 {
     pub fn start_child_span(&self, name: String) -> Option<T::Span> {
         match self.tracer() {
-            Some(tracer) => match self.span() {
-                Some(span_rc) => {
-                    let parent_context: Option<&T::SpanContext> = Some(span_rc.context());
-                    Some(T::start_span(&tracer, name, parent_context))
-                }
+            Some(tracer) => match self.span_context() {
+                Some(parent_context) => Some(T::start_span(&tracer, name, Some(&parent_context))),
                 None => {
                     trace!("Starting child span but no parent exists");
                     Some(tracer.start_span(name, None))
@@ -61,7 +67,7 @@ where
 
 impl<T> TracedRequest<T> for ServiceRequest
 where
-    T: Tracer<'static, Carrier = HashMap<String, String>> + 'static,
+    T: Tracer<'static> + 'static,
 {
     fn tracer(&self) -> Option<Rc<T>> {
         self.extensions()
@@ -73,41 +79,24 @@ where
         self.extensions_mut().insert(Some(tracer.clone()));
     }
 
-    fn parent_span_context(&self) -> Option<T::SpanContext> {
-        self.tracer().and_then(|tracer: Rc<T>| {
-            let carrier = self
-                .headers()
-                .iter()
-                .flat_map(|(key, value)| {
-                    let result: Option<(String, String)> = if let Some(value) = value.to_str().ok()
-                    {
-                        Some((key.as_str().into(), value.into()))
-                    } else {
-                        None
-                    };
-
-                    result
-                })
-                .collect();
-
-            tracer.extract(&"headers", &carrier).ok()
+    fn parent_span_context(&self) -> Result<Option<T::SpanContext>, T::Error> {
+        self.tracer().map_or(Ok(None), |tracer: Rc<T>| {
+            tracer.extract(&"headers", &HeaderMapReader::new(self.headers()))
         })
     }
 
-    fn span(&self) -> Option<Rc<T::Span>> {
-        self.extensions()
-            .get::<Rc<T::Span>>()
-            .map(|span| span.clone())
+    fn span_context(&self) -> Option<T::SpanContext> {
+        self.extensions().get::<T::SpanContext>().cloned()
     }
 
-    fn set_span(&self, span: T::Span) {
-        self.extensions_mut().insert(Rc::new(span));
+    fn set_span_context(&self, span_context: T::SpanContext) {
+        self.extensions_mut().insert(span_context);
     }
 }
 
 impl<T> TracedRequest<T> for HttpRequest
 where
-    T: Tracer<'static, Carrier = HashMap<String, String>> + 'static,
+    T: Tracer<'static> + 'static,
 {
     fn tracer(&self) -> Option<Rc<T>> {
         self.extensions()
@@ -119,51 +108,66 @@ where
         self.extensions_mut().insert(tracer.clone());
     }
 
-    fn parent_span_context(&self) -> Option<T::SpanContext> {
-        self.tracer().and_then(|tracer: Rc<T>| {
-            let carrier = self
-                .headers()
-                .iter()
-                .flat_map(|(key, value)| {
-                    let result: Option<(String, String)> = if let Some(value) = value.to_str().ok()
-                    {
-                        Some((key.as_str().into(), value.into()))
-                    } else {
-                        None
-                    };
-
-                    result
-                })
-                .collect();
-
-            tracer.extract(&"headers", &carrier).ok()
+    fn parent_span_context(&self) -> Result<Option<T::SpanContext>, T::Error> {
+        self.tracer().map_or(Ok(None), |tracer: Rc<T>| {
+            tracer.extract(&"headers", &HeaderMapReader::new(self.headers()))
         })
     }
 
-    fn span(&self) -> Option<Rc<T::Span>> {
-        self.extensions()
-            .get::<Rc<T::Span>>()
-            .map(|span| span.clone())
+    fn span_context(&self) -> Option<T::SpanContext> {
+        self.extensions().get::<T::SpanContext>().cloned()
     }
 
-    fn set_span(&self, span: T::Span) {
-        self.extensions_mut().insert(Rc::new(span));
+    fn set_span_context(&self, span_context: T::SpanContext) {
+        self.extensions_mut().insert(span_context);
     }
 }
 
-pub struct HttpRequestTracerFuture<S, SP, B>
+pub struct HttpRequestTracerFuture<T, S, B>
 where
+    T: Tracer<'static>,
     S: Service,
-    SP: Span<'static>,
 {
     fut: S::Future,
-    span: Rc<SP>,
+    tracer: Rc<T>,
+    context: Option<T::SpanContext>,
+    span: Option<SpanGuard<'static, T::Span>>,
     _t: PhantomData<(B,)>,
 }
 
+/// Keeps the request's span context active until dropped.
+struct Activation<'m, C: 'm> {
+    scope_manager: &'m ScopeManager<C>,
+    context: &'m C,
+}
+
+impl<'m, C: Clone + 'm> Activation<'m, C> {
+    fn new<T>(tracer: &'m T, context: Option<&'m C>) -> Option<Self>
+    where
+        T: Tracer<'static, SpanContext = C>,
+    {
+        match (tracer.scope_manager(), context) {
+            (Some(scope_manager), Some(context)) => {
+                scope_manager.activate(context.clone());
+                Some(Activation {
+                    scope_manager,
+                    context,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<'m, C: 'm> Drop for Activation<'m, C> {
+    fn drop(&mut self) {
+        self.scope_manager.deactivate(self.context);
+    }
+}
+
 pub struct HttpRequestTracerService<T, S>
 where
-    T: Tracer<'static, Carrier = HashMap<String, String>> + 'static,
+    T: Tracer<'static> + 'static,
 {
     pub tracer: Rc<T>,
     service: S,
@@ -171,14 +175,14 @@ where
 
 impl<T, S, B> Service for HttpRequestTracerService<T, S>
 where
-    T: Tracer<'static, Carrier = HashMap<String, String>> + 'static,
+    T: Tracer<'static> + 'static,
     S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = ActixWebError>,
     B: MessageBody,
 {
     type Request = ServiceRequest;
     type Response = ServiceResponse<TracedBody<B, T::SpanContext>>;
     type Error = S::Error;
-    type Future = HttpRequestTracerFuture<S, T::Span, B>;
+    type Future = HttpRequestTracerFuture<T, S, B>;
 
     fn poll_ready(&mut self) -> Poll<(), Self::Error> {
         self.service.poll_ready()
@@ -187,50 +191,95 @@ where
     fn call(&mut self, req: Self::Request) -> Self::Future {
         req.set_tracer(self.tracer.clone());
 
-        let context = <ServiceRequest as TracedRequest<T>>::parent_span_context(&req);
+        let (context, extract_error) =
+            match <ServiceRequest as TracedRequest<T>>::parent_span_context(&req) {
+                Ok(context) => (context, None),
+                Err(error) => (None, Some(error)),
+            };
 
-        let span = match req.extensions().get::<Rc<T::Span>>() {
-            Some(span) => span.clone(),
+        let existing_span_context = <ServiceRequest as TracedRequest<T>>::span_context(&req);
+
+        let span = match existing_span_context {
+            Some(_) => None,
             None => {
-                let mut span = self
+                // The request is the root of its work on this server, so it must not
+                // pick up whatever span happens to be active on the worker thread.
+                let mut builder = self
                     .tracer
-                    .start_span(format!("HTTP ({})", req.method()), context.as_ref());
-
-                span.set_tag("http.uri", TagValue::String(format!("{}", req.uri())));
-                span.set_tag("http.method", TagValue::String(req.method().to_string()));
+                    .span_builder(format!("HTTP ({})", req.method()))
+                    .ignore_active_span();
+                if let Some(ref context) = context {
+                    builder = builder.child_of(context);
+                }
+                let mut span = builder.start();
+
+                span.set_span_kind(SpanKind::Server);
+                // Kept as `http.uri` rather than the conventional `http.url`, which
+                // is what existing queries against this middleware's spans use.
+                span.set_tag("http.uri", req.uri().to_string());
+                span.set_http_method(req.method().as_str());
+
+                if let Some(error) = extract_error {
+                    trace!("Could not extract incoming span context: {}", error);
+                    span.log(vec![
+                        ("event", TagValue::String("extract_error".to_owned())),
+                        ("message", TagValue::String(error.to_string())),
+                    ]);
+                }
 
-                let span = Rc::new(span);
-                span
+                <ServiceRequest as TracedRequest<T>>::set_span_context(
+                    &req,
+                    span.context().clone(),
+                );
+                Some(SpanGuard::new(span))
             }
         };
 
-        req.extensions_mut().insert(span.clone());
+        // Actix calls the inner services here and runs handlers, extractors and
+        // any middleware futures as the returned future is polled, so the
+        // request's span is made active for both. That way spans and log lines
+        // from handlers pick it up without going through the request.
+        let context = <ServiceRequest as TracedRequest<T>>::span_context(&req);
+        let fut = {
+            let _activation = Activation::new(&*self.tracer, context.as_ref());
+            self.service.call(req)
+        };
 
         HttpRequestTracerFuture {
-            fut: self.service.call(req),
+            fut,
+            tracer: self.tracer.clone(),
+            context,
             span,
             _t: PhantomData,
         }
     }
 }
 
-impl<S, SP, B> Future for HttpRequestTracerFuture<S, SP, B>
+impl<T, S, B> Future for HttpRequestTracerFuture<T, S, B>
 where
+    T: Tracer<'static>,
     S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = ActixWebError>,
-    SP: Span<'static>,
     B: MessageBody,
 {
-    type Item = ServiceResponse<TracedBody<B, SP::Context>>;
+    type Item = ServiceResponse<TracedBody<B, T::SpanContext>>;
     type Error = S::Error;
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
         trace!("Polling for body");
 
-        match self.fut.poll() {
+        let polled = {
+            let _activation = Activation::new(&*self.tracer, self.context.as_ref());
+            self.fut.poll()
+        };
+
+        match polled {
             Ok(state) => match state {
-                Async::Ready(result) => Ok(Async::Ready(result.map_body(move |_, body| {
+                Async::Ready(result) => Ok(Async::Ready(result.map_body(move |head, body| {
                     trace!("Got body, finishing span");
-                    let finished_span = self.span.finish();
+                    let finished_span = self.span.take().map(|mut span| {
+                        span.set_http_status(head.status.as_u16());
+                        span.finish()
+                    });
 
                     ResponseBody::Body(TracedBody {
                         body,
@@ -239,14 +288,20 @@ where
                 }))),
                 Async::NotReady => Ok(Async::NotReady),
             },
-            Err(error) => Err(error),
+            Err(error) => {
+                if let Some(mut span) = self.span.take() {
+                    span.set_error(true);
+                    span.finish();
+                }
+                Err(error)
+            }
         }
     }
 }
 
 impl<T, S, B> Transform<S> for HttpRequestTracer<T>
 where
-    T: Tracer<'static, Carrier = HashMap<String, String>> + 'static,
+    T: Tracer<'static> + 'static,
     S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = ActixWebError>,
     B: MessageBody,
 {
@@ -268,7 +323,7 @@ where
 
 pub struct TracedBody<B, C> {
     body: ResponseBody<B>,
-    _finished_span: FinishedSpan<C>,
+    _finished_span: Option<FinishedSpan<C>>,
 }
 
 impl<B: MessageBody, C> MessageBody for TracedBody<B, C> {
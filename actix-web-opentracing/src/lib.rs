@@ -7,6 +7,8 @@ extern crate log;
 extern crate opentracing_api;
 extern crate opentracing_rust_wip;
 
+mod header_map_carrier;
 mod http_request_tracer;
 
+pub use header_map_carrier::{HeaderMapReader, HeaderMapWriter};
 pub use http_request_tracer::{HttpRequestTracer, TracedRequest};
@@ -0,0 +1,304 @@
+use jaeger_thrift::baggage::{
+    BaggageRestriction as ThriftBaggageRestriction, BaggageRestrictionManagerSyncClient,
+    TBaggageRestrictionManagerSyncClient,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, Weak};
+use std::thread;
+use std::time::Duration;
+use thrift;
+use thrift::protocol::{TBinaryInputProtocol, TBinaryOutputProtocol};
+use thrift::transport::{TBufferedReadTransport, TBufferedWriteTransport, TIoChannel, TTcpChannel};
+
+/// Default maximum length of a baggage value, matching the other Jaeger clients.
+pub const DEFAULT_MAX_VALUE_LENGTH: usize = 2048;
+
+/// Describes whether a baggage key may be set and how long its value may be.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaggageRestriction {
+    pub key_allowed: bool,
+    pub max_value_length: usize,
+}
+
+impl BaggageRestriction {
+    pub fn new(key_allowed: bool, max_value_length: usize) -> Self {
+        BaggageRestriction {
+            key_allowed,
+            max_value_length,
+        }
+    }
+
+    fn denied() -> Self {
+        Self::new(false, 0)
+    }
+}
+
+/// Decides which baggage keys a span may carry.
+pub trait BaggageRestrictionManager {
+    /// Returns the restriction that applies to the given baggage key.
+    fn get_restriction(&self, key: &str) -> BaggageRestriction;
+}
+
+/// Allows every baggage key up to `max_value_length`.
+pub struct DefaultBaggageRestrictionManager {
+    max_value_length: usize,
+}
+
+impl DefaultBaggageRestrictionManager {
+    pub fn new(max_value_length: usize) -> Self {
+        DefaultBaggageRestrictionManager { max_value_length }
+    }
+}
+
+impl Default for DefaultBaggageRestrictionManager {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_VALUE_LENGTH)
+    }
+}
+
+impl BaggageRestrictionManager for DefaultBaggageRestrictionManager {
+    fn get_restriction(&self, _key: &str) -> BaggageRestriction {
+        BaggageRestriction::new(true, self.max_value_length)
+    }
+}
+
+#[derive(Default)]
+struct RestrictionState {
+    initialized: bool,
+    restrictions: HashMap<String, BaggageRestriction>,
+}
+
+/// Periodically fetches baggage restrictions for a service from a remote
+/// `BaggageRestrictionManager` thrift service and enforces them.
+///
+/// Until the first successful fetch every key is either denied or allowed,
+/// depending on `deny_baggage_on_initialization_failure`. Once restrictions
+/// have been fetched, keys that are not listed are denied.
+pub struct RemoteBaggageRestrictionManager {
+    state: Arc<RwLock<RestrictionState>>,
+    deny_baggage_on_initialization_failure: bool,
+}
+
+impl RemoteBaggageRestrictionManager {
+    pub fn new(
+        service_name: String,
+        host_port: String,
+        refresh_interval: Duration,
+        deny_baggage_on_initialization_failure: bool,
+    ) -> Self {
+        let state = Arc::new(RwLock::new(RestrictionState::default()));
+        let weak_state = Arc::downgrade(&state);
+
+        thread::spawn(move || {
+            Self::poll_restrictions(weak_state, &service_name, &host_port, refresh_interval)
+        });
+
+        RemoteBaggageRestrictionManager {
+            state,
+            deny_baggage_on_initialization_failure,
+        }
+    }
+
+    /// Returns true once restrictions have been fetched successfully.
+    pub fn is_initialized(&self) -> bool {
+        self.state
+            .read()
+            .map(|state| state.initialized)
+            .unwrap_or(false)
+    }
+
+    fn poll_restrictions(
+        weak_state: Weak<RwLock<RestrictionState>>,
+        service_name: &str,
+        host_port: &str,
+        refresh_interval: Duration,
+    ) {
+        loop {
+            let state = match weak_state.upgrade() {
+                Some(state) => state,
+                None => {
+                    trace!("Baggage restriction manager dropped, stopping refresh");
+                    return;
+                }
+            };
+
+            match fetch_restrictions(service_name, host_port) {
+                Ok(restrictions) => {
+                    trace!("Fetched {} baggage restrictions", restrictions.len());
+                    if let Ok(mut state) = state.write() {
+                        state.restrictions = restrictions
+                            .into_iter()
+                            .map(|restriction| {
+                                let max_value_length = if restriction.max_value_length > 0 {
+                                    restriction.max_value_length as usize
+                                } else {
+                                    0
+                                };
+
+                                (
+                                    restriction.baggage_key,
+                                    BaggageRestriction::new(true, max_value_length),
+                                )
+                            })
+                            .collect();
+                        state.initialized = true;
+                    }
+                }
+                Err(error) => warn!("Got an error fetching baggage restrictions: {}", error),
+            }
+
+            drop(state);
+            thread::sleep(refresh_interval);
+        }
+    }
+}
+
+impl BaggageRestrictionManager for RemoteBaggageRestrictionManager {
+    fn get_restriction(&self, key: &str) -> BaggageRestriction {
+        let state = match self.state.read() {
+            Ok(state) => state,
+            Err(_) => return BaggageRestriction::denied(),
+        };
+
+        if !state.initialized {
+            if self.deny_baggage_on_initialization_failure {
+                return BaggageRestriction::denied();
+            } else {
+                return BaggageRestriction::new(true, DEFAULT_MAX_VALUE_LENGTH);
+            }
+        }
+
+        state
+            .restrictions
+            .get(key)
+            .cloned()
+            .unwrap_or_else(BaggageRestriction::denied)
+    }
+}
+
+fn fetch_restrictions(
+    service_name: &str,
+    host_port: &str,
+) -> thrift::Result<Vec<ThriftBaggageRestriction>> {
+    let mut channel = TTcpChannel::new();
+    channel.open(host_port)?;
+    let (input_channel, output_channel) = channel.split()?;
+
+    let input_protocol =
+        TBinaryInputProtocol::new(TBufferedReadTransport::new(input_channel), true);
+    let output_protocol =
+        TBinaryOutputProtocol::new(TBufferedWriteTransport::new(output_channel), true);
+
+    let mut client = BaggageRestrictionManagerSyncClient::new(input_protocol, output_protocol);
+    client.get_baggage_restrictions(service_name.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jaeger_thrift::baggage::{
+        BaggageRestrictionManagerSyncHandler, BaggageRestrictionManagerSyncProcessor,
+    };
+    use std::net::TcpListener;
+    use std::time::Instant;
+    use thrift::server::TProcessor;
+
+    struct StandInHandler;
+
+    impl BaggageRestrictionManagerSyncHandler for StandInHandler {
+        fn handle_get_baggage_restrictions(
+            &self,
+            service_name: String,
+        ) -> thrift::Result<Vec<ThriftBaggageRestriction>> {
+            assert_eq!(service_name, "test-service");
+            Ok(vec![ThriftBaggageRestriction::new("user-id".to_owned(), 8)])
+        }
+    }
+
+    /// Serves `StandInHandler` on an ephemeral port and returns its address.
+    fn start_stand_in_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("should bind stand-in server");
+        let host_port = listener
+            .local_addr()
+            .expect("should have local address")
+            .to_string();
+
+        thread::spawn(move || {
+            let processor = BaggageRestrictionManagerSyncProcessor::new(StandInHandler);
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                let (input_channel, output_channel) =
+                    match TTcpChannel::with_stream(stream).split() {
+                        Ok(channels) => channels,
+                        Err(_) => continue,
+                    };
+
+                let mut input_protocol =
+                    TBinaryInputProtocol::new(TBufferedReadTransport::new(input_channel), true);
+                let mut output_protocol =
+                    TBinaryOutputProtocol::new(TBufferedWriteTransport::new(output_channel), true);
+                while processor
+                    .process(&mut input_protocol, &mut output_protocol)
+                    .is_ok()
+                {}
+            }
+        });
+
+        host_port
+    }
+
+    #[test]
+    fn test_uninitialized_restrictions() {
+        let allowing = RemoteBaggageRestrictionManager::new(
+            "test-service".to_owned(),
+            "127.0.0.1:1".to_owned(),
+            Duration::from_secs(60),
+            false,
+        );
+        assert_eq!(
+            allowing.get_restriction("anything"),
+            BaggageRestriction::new(true, DEFAULT_MAX_VALUE_LENGTH)
+        );
+
+        let denying = RemoteBaggageRestrictionManager::new(
+            "test-service".to_owned(),
+            "127.0.0.1:1".to_owned(),
+            Duration::from_secs(60),
+            true,
+        );
+        assert_eq!(
+            denying.get_restriction("anything"),
+            BaggageRestriction::denied()
+        );
+    }
+
+    #[test]
+    fn test_remote_restrictions() {
+        let host_port = start_stand_in_server();
+
+        let manager = RemoteBaggageRestrictionManager::new(
+            "test-service".to_owned(),
+            host_port,
+            Duration::from_millis(50),
+            true,
+        );
+
+        let started = Instant::now();
+        while !manager.is_initialized() && started.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        assert!(manager.is_initialized());
+        assert_eq!(
+            manager.get_restriction("user-id"),
+            BaggageRestriction::new(true, 8)
+        );
+        assert_eq!(
+            manager.get_restriction("other"),
+            BaggageRestriction::denied()
+        );
+    }
+}
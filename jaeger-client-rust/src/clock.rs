@@ -0,0 +1,73 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const NANOS_PER_MICRO: u64 = 1000;
+const MICROS_PER_SEC: u64 = 1000_000;
+
+/// Source of the timestamps and durations recorded on spans.
+pub trait Clock {
+    /// Wall-clock time in microseconds since the UNIX epoch.
+    fn timestamp(&self) -> u64;
+
+    /// Monotonic time used to measure durations.
+    fn now(&self) -> Instant;
+}
+
+/// Converts a duration into whole microseconds.
+pub fn duration_to_micros(duration: Duration) -> u64 {
+    duration.as_secs() * MICROS_PER_SEC + (u64::from(duration.subsec_nanos()) / NANOS_PER_MICRO)
+}
+
+/// Reads the system wall clock and monotonic clock.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn timestamp(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(duration_to_micros)
+            .unwrap_or(0)
+    }
+
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to, for deterministic tests.
+///
+/// Clones share the same time, so a test can keep one and hand another to the tracer.
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    start_timestamp: u64,
+    start_instant: Instant,
+    elapsed: Rc<Cell<u64>>,
+}
+
+impl ManualClock {
+    /// Creates a clock whose wall time starts at `start_timestamp` microseconds.
+    pub fn new(start_timestamp: u64) -> Self {
+        ManualClock {
+            start_timestamp,
+            start_instant: Instant::now(),
+            elapsed: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Moves the clock forward by the given number of microseconds.
+    pub fn advance(&self, micros: u64) {
+        self.elapsed.set(self.elapsed.get() + micros);
+    }
+}
+
+impl Clock for ManualClock {
+    fn timestamp(&self) -> u64 {
+        self.start_timestamp + self.elapsed.get()
+    }
+
+    fn now(&self) -> Instant {
+        self.start_instant + Duration::from_micros(self.elapsed.get())
+    }
+}
@@ -1,7 +1,11 @@
+use opentracing_rust_wip::TextMapReader;
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
 use tracer::Codec;
 
-use span::SpanContext;
+use span::{convert_hex_to_u64, SpanContext, TraceId, TraceIdParseError};
 
 pub struct Extractor {}
 
@@ -15,20 +19,212 @@ const B3_HEADERS: [&str; 7] = [
     "x-ot-span-context",
 ];
 
+const B3_TRACE_ID: &str = "x-b3-traceid";
+const B3_SPAN_ID: &str = "x-b3-spanid";
+const B3_PARENT_SPAN_ID: &str = "x-b3-parentspanid";
+const B3_SAMPLED: &str = "x-b3-sampled";
+const B3_SINGLE: &str = "b3";
+
+/// Describes why a span context could not be extracted from a carrier.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ExtractError {
+    /// Some trace headers were present, but this required one was not.
+    MissingHeader(&'static str),
+    /// The header value is not valid hexadecimal.
+    MalformedHex { header: String, value: String },
+    /// The header value has more hex digits than the id can hold.
+    IdTooLong { header: String, value: String },
+    /// The header carries an id of zero, which is never valid.
+    ZeroId(String),
+    /// The single `b3` header and the `x-b3-*` headers describe different spans.
+    ConflictingFormats,
+}
+
+impl Error for ExtractError {
+    fn description(&self) -> &str {
+        match *self {
+            ExtractError::MissingHeader(_) => "Missing trace header",
+            ExtractError::MalformedHex { .. } => "Malformed hex id",
+            ExtractError::IdTooLong { .. } => "Id too long",
+            ExtractError::ZeroId(_) => "Zero id",
+            ExtractError::ConflictingFormats => "Conflicting trace header formats",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        None
+    }
+}
+
+impl fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExtractError::MissingHeader(header) => write!(f, "{}: {}", self.description(), header),
+            ExtractError::MalformedHex {
+                ref header,
+                ref value,
+            }
+            | ExtractError::IdTooLong {
+                ref header,
+                ref value,
+            } => write!(f, "{}: {}={:?}", self.description(), header, value),
+            ExtractError::ZeroId(ref header) => write!(f, "{}: {}", self.description(), header),
+            ExtractError::ConflictingFormats => write!(f, "{}", self.description()),
+        }
+    }
+}
+
+fn parse_trace_id(header: &str, value: &String) -> Result<TraceId, ExtractError> {
+    let trace_id = TraceId::try_from(value).map_err(|error| match error {
+        TraceIdParseError::TooLong => ExtractError::IdTooLong {
+            header: header.to_owned(),
+            value: value.clone(),
+        },
+        TraceIdParseError::ParseIntError(_) => ExtractError::MalformedHex {
+            header: header.to_owned(),
+            value: value.clone(),
+        },
+    })?;
+
+    if trace_id.low == 0 && trace_id.high == 0 {
+        return Err(ExtractError::ZeroId(header.to_owned()));
+    }
+
+    Ok(trace_id)
+}
+
+fn parse_span_id(header: &str, value: &str) -> Result<u64, ExtractError> {
+    if value.len() > 16 {
+        return Err(ExtractError::IdTooLong {
+            header: header.to_owned(),
+            value: value.to_owned(),
+        });
+    }
+
+    let span_id = convert_hex_to_u64(value).map_err(|_| ExtractError::MalformedHex {
+        header: header.to_owned(),
+        value: value.to_owned(),
+    })?;
+
+    if span_id == 0 {
+        return Err(ExtractError::ZeroId(header.to_owned()));
+    }
+
+    Ok(span_id)
+}
+
+/// Maps the single-segment `b3` sampling flags `0`, `1` and `d` (debug) to `x-b3-sampled`.
+fn sampling_flag(value: &str) -> Option<&'static str> {
+    match value {
+        "0" => Some("0"),
+        "1" | "d" => Some("1"),
+        _ => None,
+    }
+}
+
+/// Splits a single `b3: {traceid}-{spanid}[-{sampled}[-{parentspanid}]]` header into
+/// its `x-b3-*` equivalents. A lone `b3: {sampled}` only carries the sampling decision.
+fn expand_single_header(value: &str) -> Result<HashMap<String, String>, ExtractError> {
+    let mut parts = value.split('-');
+    let mut baggage = HashMap::new();
+
+    match (parts.next(), parts.next()) {
+        (Some(trace_id), Some(span_id)) => {
+            baggage.insert(B3_TRACE_ID.to_owned(), trace_id.to_owned());
+            baggage.insert(B3_SPAN_ID.to_owned(), span_id.to_owned());
+        }
+        (Some(flag), None) => match sampling_flag(flag) {
+            Some(sampled) => {
+                baggage.insert(B3_SAMPLED.to_owned(), sampled.to_owned());
+                return Ok(baggage);
+            }
+            None => return Err(ExtractError::MissingHeader(B3_SPAN_ID)),
+        },
+        _ => return Err(ExtractError::MissingHeader(B3_TRACE_ID)),
+    }
+
+    if let Some(sampled) = parts.next() {
+        let sampled = if sampled == "d" { "1" } else { sampled };
+        baggage.insert(B3_SAMPLED.to_owned(), sampled.to_owned());
+    }
+
+    if let Some(parent_span_id) = parts.next() {
+        baggage.insert(B3_PARENT_SPAN_ID.to_owned(), parent_span_id.to_owned());
+    }
+
+    Ok(baggage)
+}
+
+fn validate(baggage: &HashMap<String, String>) -> Result<(TraceId, u64), ExtractError> {
+    let trace_id = baggage
+        .get(B3_TRACE_ID)
+        .ok_or(ExtractError::MissingHeader(B3_TRACE_ID))
+        .and_then(|value| parse_trace_id(B3_TRACE_ID, value))?;
+
+    let span_id = baggage
+        .get(B3_SPAN_ID)
+        .ok_or(ExtractError::MissingHeader(B3_SPAN_ID))
+        .and_then(|value| parse_span_id(B3_SPAN_ID, value))?;
+
+    if let Some(parent_span_id) = baggage.get(B3_PARENT_SPAN_ID) {
+        parse_span_id(B3_PARENT_SPAN_ID, parent_span_id)?;
+    }
+
+    Ok((trace_id, span_id))
+}
+
 impl Extractor {
-    pub fn extract(codec: &Codec, carrier: &HashMap<String, String>) -> Option<SpanContext> {
+    /// Extracts a span context from the carrier.
+    ///
+    /// Returns `Ok(None)` if the carrier holds no trace headers at all, and an
+    /// `ExtractError` if the headers it holds are incomplete or malformed.
+    ///
+    /// A carrier with neither a trace id nor a span id, such as `b3: 0` or a
+    /// lone `x-b3-sampled` or `x-request-id`, yields a context without ids.
+    /// Spans started from it begin a new trace that keeps its sampling decision.
+    pub fn extract<R>(codec: &Codec, carrier: &R) -> Result<Option<SpanContext>, ExtractError>
+    where
+        R: TextMapReader + ?Sized,
+    {
         match codec {
             Codec::ZipkinB3TextMap => {
-                let baggage: HashMap<String, String> = carrier
+                let mut baggage: HashMap<String, String> = B3_HEADERS
                     .iter()
-                    .filter(|(name, _value)| B3_HEADERS.contains(&name.as_str()))
-                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .flat_map(|name| {
+                        carrier
+                            .get_first(name)
+                            .map(|value| ((*name).to_owned(), value.to_owned()))
+                    })
                     .collect();
 
+                let has_multi_headers = baggage.contains_key(B3_TRACE_ID)
+                    || baggage.contains_key(B3_SPAN_ID);
+
+                if let Some(single) = carrier.get_first(B3_SINGLE) {
+                    let expanded = expand_single_header(single)?;
+
+                    if expanded.contains_key(B3_TRACE_ID) {
+                        let (trace_id, span_id) = validate(&expanded)?;
+
+                        if has_multi_headers {
+                            let (multi_trace_id, multi_span_id) = validate(&baggage)?;
+                            if multi_trace_id != trace_id || multi_span_id != span_id {
+                                return Err(ExtractError::ConflictingFormats);
+                            }
+                        }
+                    } else if has_multi_headers {
+                        validate(&baggage)?;
+                    }
+
+                    baggage.extend(expanded);
+                } else if has_multi_headers {
+                    validate(&baggage)?;
+                }
+
                 if baggage.is_empty() {
-                    None
+                    Ok(None)
                 } else {
-                    Some(SpanContext::from(baggage))
+                    Ok(Some(SpanContext::from(baggage)))
                 }
             }
         }
@@ -0,0 +1,49 @@
+use rand::rngs::StdRng;
+use rand::{random, RngCore, SeedableRng};
+use std::cell::RefCell;
+
+/// Generates the ids used for new traces and spans.
+pub trait IdGenerator {
+    /// Returns a new random id. Implementations must never return zero.
+    fn next_id(&self) -> u64;
+}
+
+/// Draws ids from the thread-local random number generator.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct RandomIdGenerator;
+
+impl IdGenerator for RandomIdGenerator {
+    fn next_id(&self) -> u64 {
+        loop {
+            let id = random::<u64>();
+            if id != 0 {
+                return id;
+            }
+        }
+    }
+}
+
+/// Produces the same sequence of ids for the same seed, for deterministic tests.
+pub struct SeededIdGenerator {
+    rng: RefCell<StdRng>,
+}
+
+impl SeededIdGenerator {
+    pub fn new(seed: u64) -> Self {
+        SeededIdGenerator {
+            rng: RefCell::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl IdGenerator for SeededIdGenerator {
+    fn next_id(&self) -> u64 {
+        let mut rng = self.rng.borrow_mut();
+        loop {
+            let id = rng.next_u64();
+            if id != 0 {
+                return id;
+            }
+        }
+    }
+}
@@ -1,20 +1,19 @@
 use opentracing_api::SpanContext as OpentraingSpanContext;
+use opentracing_rust_wip::TextMapWriter;
 use span::SpanContext;
-use std::collections::HashMap;
 use tracer::Codec;
 
 pub struct Injector {}
 
 impl Injector {
-    pub fn inject(
-        codec: &Codec,
-        span_context: &SpanContext,
-        carrier: &mut HashMap<String, String>,
-    ) {
+    pub fn inject<W>(codec: &Codec, span_context: &SpanContext, carrier: &mut W)
+    where
+        W: TextMapWriter + ?Sized,
+    {
         match codec {
             Codec::ZipkinB3TextMap => {
                 for (key, value) in span_context.baggage_items() {
-                    carrier.insert(key.clone(), value.clone());
+                    carrier.set(key, value);
                 }
             }
         }
@@ -0,0 +1,129 @@
+use futures::{Async, Future, Poll, Stream};
+use opentracing_rust_wip::{Span as OpentracingSpan, TagValue, Tags, Tracer as OpentracingTracer};
+use std::any::type_name;
+use std::fmt::Debug;
+
+use span::Span;
+use tracer::Tracer;
+
+/// Runs futures and streams inside a span.
+///
+/// The span is active while the inner future or stream is polled, so spans
+/// started from within it become its children. It is finished when the future
+/// completes, the stream ends, or either fails. Errors only need to implement
+/// `Debug`, so that `()` and `actix_web::Error` work too: the span gets the
+/// `error` tag and an `event=error` log with the error's type as `error.kind`
+/// and its `Debug` form as `error.object`.
+///
+/// ```ignore
+/// let query = database
+///     .query(sql)
+///     .in_span(&tracer, "query")
+///     .and_then(|rows| render(rows).in_span(&tracer, "render"));
+/// ```
+pub trait Instrument: Sized {
+    /// Runs inside the given span.
+    fn instrument(self, span: Span) -> Instrumented<Self> {
+        Instrumented {
+            inner: self,
+            span: Some(span),
+        }
+    }
+
+    /// Runs inside a new span that is a child of the currently active span.
+    fn in_span<S>(self, tracer: &Tracer, operation_name: S) -> Instrumented<Self>
+    where
+        S: Into<String>,
+    {
+        self.instrument(tracer.span_builder(operation_name).start())
+    }
+}
+
+impl<T> Instrument for T {}
+
+/// A future or stream that runs inside a span, see `Instrument`.
+pub struct Instrumented<T> {
+    inner: T,
+    span: Option<Span>,
+}
+
+impl<T> Instrumented<T> {
+    /// The span, until it has been finished.
+    pub fn span(&self) -> Option<&Span> {
+        self.span.as_ref()
+    }
+
+    fn poll_in_span<F, R>(&mut self, poll: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let inner = &mut self.inner;
+        match self.span {
+            Some(ref span) => span.in_scope(|| poll(inner)),
+            None => poll(inner),
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(span) = self.span.take() {
+            span.finish();
+        }
+    }
+
+    fn finish_with_error<E: Debug>(&mut self, error: &E) {
+        if let Some(mut span) = self.span.take() {
+            span.set_tag(Tags::Error.as_str(), true);
+            span.log(vec![
+                ("event", TagValue::String("error".to_owned())),
+                ("error.kind", TagValue::String(type_name::<E>().to_owned())),
+                ("error.object", TagValue::String(format!("{:?}", error))),
+            ]);
+            span.finish();
+        }
+    }
+}
+
+impl<T> Future for Instrumented<T>
+where
+    T: Future,
+    T::Error: Debug,
+{
+    type Item = T::Item;
+    type Error = T::Error;
+
+    fn poll(&mut self) -> Poll<T::Item, T::Error> {
+        let result = self.poll_in_span(|inner| inner.poll());
+        match result {
+            Ok(Async::Ready(_)) => self.finish(),
+            Ok(Async::NotReady) => {}
+            Err(ref error) => self.finish_with_error(error),
+        }
+        result
+    }
+}
+
+impl<T> Stream for Instrumented<T>
+where
+    T: Stream,
+    T::Error: Debug,
+{
+    type Item = T::Item;
+    type Error = T::Error;
+
+    fn poll(&mut self) -> Poll<Option<T::Item>, T::Error> {
+        let result = self.poll_in_span(|inner| inner.poll());
+        match result {
+            Ok(Async::Ready(None)) => self.finish(),
+            Ok(_) => {}
+            Err(ref error) => self.finish_with_error(error),
+        }
+        result
+    }
+}
+
+impl<T> Drop for Instrumented<T> {
+    fn drop(&mut self) {
+        // Record abandoned work too, e.g. a future dropped on timeout.
+        self.finish();
+    }
+}
@@ -1,30 +1,106 @@
 extern crate env_logger;
+extern crate futures;
 extern crate opentracing_rust_wip;
 #[macro_use]
 extern crate log;
 extern crate jaeger_thrift;
 extern crate opentracing_api;
+#[cfg(test)]
+extern crate opentracing_rust_wip_macros;
 extern crate ordered_float;
 extern crate rand;
 extern crate thrift;
+#[cfg(all(test, feature = "tracing-layer"))]
+#[macro_use(event, info_span)]
+extern crate tracing;
+#[cfg(feature = "tracing-layer")]
+extern crate tracing_core;
+#[cfg(feature = "tracing-layer")]
+extern crate tracing_subscriber;
 
+mod baggage;
+mod clock;
 mod extractor;
+mod id_generator;
 mod injector;
+mod instrument;
+mod limits;
+mod log_format;
+mod observer;
+mod processor;
 mod reporter;
+mod scope_manager;
 mod span;
+mod span_logger;
+#[cfg(feature = "tracing-layer")]
+mod thread_tracer;
 mod tracer;
+#[cfg(feature = "tracing-layer")]
+mod tracing_layer;
 
-pub use extractor::Extractor;
+pub use baggage::{
+    BaggageRestriction, BaggageRestrictionManager, DefaultBaggageRestrictionManager,
+    RemoteBaggageRestrictionManager,
+};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use extractor::{ExtractError, Extractor};
+pub use id_generator::{IdGenerator, RandomIdGenerator, SeededIdGenerator};
 pub use injector::Injector;
+pub use instrument::{Instrument, Instrumented};
+pub use limits::SpanLimits;
+pub use log_format::{format_with_span_context, TraceContextFormat, TraceIds};
+pub use observer::SpanObserver;
+pub use processor::{Redactor, RemoveTags, SpanProcessor};
 pub use reporter::RemoteReporter;
+pub use scope_manager::ThreadLocalScopeManager;
 pub use span::{Span, SpanContext, TraceId};
-pub use tracer::Tracer;
+pub use span_logger::SpanLogger;
+pub use tracer::{Error, Tracer};
+#[cfg(feature = "tracing-layer")]
+pub use tracing_layer::JaegerLayer;
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use opentracing_rust_wip::Tracer as OpentracingTracer;
+    use log::{Level, Log, Record};
+    use opentracing_rust_wip::{
+        Reporter, Span as OpentracingSpan, TagValue, Tracer as OpentracingTracer,
+    };
+    use opentracing_rust_wip_macros::traced;
+    use std::cell::RefCell;
     use std::collections::HashMap;
+    use std::num::ParseIntError;
+    use std::rc::Rc;
+
+    /// The parts of a reported span that the tests look at.
+    #[derive(Debug)]
+    struct ReportedSpan {
+        operation_name: String,
+        tags: HashMap<String, TagValue>,
+        logs: Vec<(u64, HashMap<String, TagValue>)>,
+        duration: u64,
+        incomplete: bool,
+    }
+
+    /// Records every span reported to it; clones share the same records.
+    #[derive(Clone, Default)]
+    struct CapturingReporter {
+        spans: Rc<RefCell<Vec<ReportedSpan>>>,
+    }
+
+    impl<'a> Reporter<'a> for CapturingReporter {
+        type Span = Span;
+
+        fn report(&self, span: &Span) {
+            self.spans.borrow_mut().push(ReportedSpan {
+                operation_name: span.operation_name.clone(),
+                tags: span.tags.clone(),
+                logs: span.logs.clone(),
+                duration: span.duration,
+                incomplete: span.incomplete,
+            });
+        }
+    }
 
     #[test]
     fn test_extraction() {
@@ -54,7 +130,7 @@ mod tests {
         span_context.set_trace_id(trace_id.clone());
         span_context.set_span_id(5208512171318403364);
 
-        let mut output = HashMap::new();
+        let mut output: HashMap<String, String> = HashMap::new();
         let injected = &mut output;
         let _ = tracer::Tracer::default().inject(&span_context, "header", injected);
 
@@ -78,4 +154,615 @@ mod tests {
         assert_eq!(trace_id.low, 1);
         assert_eq!(trace_id.high, 0);
     }
+
+    #[test]
+    fn test_malformed_extraction() {
+        let tracer = tracer::Tracer::default();
+
+        let mut carrier: HashMap<String, String> = HashMap::new();
+        carrier.insert("x-b3-traceid".to_owned(), "not-hex".to_owned());
+        carrier.insert("x-b3-spanid".to_owned(), "1".to_owned());
+        match tracer.extract("header", &carrier) {
+            Err(Error::UnableToExtract(ExtractError::MalformedHex { header, .. })) => {
+                assert_eq!(header, "x-b3-traceid")
+            }
+            _ => panic!("Malformed trace id should not be extracted"),
+        }
+
+        carrier.insert("x-b3-traceid".to_owned(), "1".to_owned());
+        carrier.insert("x-b3-spanid".to_owned(), "0".to_owned());
+        match tracer.extract("header", &carrier) {
+            Err(Error::UnableToExtract(ExtractError::ZeroId(header))) => {
+                assert_eq!(header, "x-b3-spanid")
+            }
+            _ => panic!("Zero span id should not be extracted"),
+        }
+
+        carrier.remove("x-b3-spanid");
+        match tracer.extract("header", &carrier) {
+            Err(Error::UnableToExtract(ExtractError::MissingHeader(header))) => {
+                assert_eq!(header, "x-b3-spanid")
+            }
+            _ => panic!("Incomplete context should not be extracted"),
+        }
+
+        let empty: HashMap<String, String> = HashMap::new();
+        assert!(tracer.extract("header", &empty).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_sampling_only_extraction() {
+        let tracer = tracer::Tracer::default();
+
+        for (value, sampled) in [("0", false), ("1", true), ("d", true)].iter() {
+            let mut carrier: HashMap<String, String> = HashMap::new();
+            carrier.insert("b3".to_owned(), (*value).to_owned());
+
+            let context = tracer
+                .extract("header", &carrier)
+                .expect("sampling flag should be extracted")
+                .expect("sampling flag should yield a context");
+            assert_eq!(context.sampled(), Some(*sampled));
+            assert!(context.trace_id().is_none());
+            assert!(context.span_id().is_none());
+        }
+
+        let mut carrier: HashMap<String, String> = HashMap::new();
+        carrier.insert("x-request-id".to_owned(), "request".to_owned());
+        carrier.insert("x-b3-sampled".to_owned(), "0".to_owned());
+        let context = tracer
+            .extract("header", &carrier)
+            .expect("sampling-only headers should be extracted")
+            .expect("sampling-only headers should yield a context");
+        assert!(context.trace_id().is_none());
+
+        let span = tracer.start_span("new-trace".to_owned(), Some(&context));
+        assert!(span.context.trace_id().is_some());
+        assert_eq!(span.context.parent_span_id(), None);
+        assert_eq!(span.context.sampled(), Some(false));
+
+        carrier.clear();
+        carrier.insert("b3".to_owned(), "abc".to_owned());
+        match tracer.extract("header", &carrier) {
+            Err(Error::UnableToExtract(ExtractError::MissingHeader(header))) => {
+                assert_eq!(header, "x-b3-spanid")
+            }
+            _ => panic!("A lone trace id should not be extracted"),
+        }
+    }
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new(1_000_000);
+        let reporter = CapturingReporter::default();
+        let tracer = tracer::Tracer::default()
+            .with_clock(clock.clone())
+            .with_reporter(reporter.clone());
+
+        let mut span = tracer.start_span("clocked".to_owned(), None);
+        assert_eq!(span.start_time, 1_000_000);
+
+        clock.advance(250);
+        span.log_event("halfway".to_owned());
+        assert_eq!(span.logs[0].0, 1_000_250);
+
+        clock.advance(250);
+        span.finish();
+        assert_eq!(reporter.spans.borrow()[0].duration, 500);
+    }
+
+    #[test]
+    fn test_monotonic_duration() {
+        use std::cell::Cell;
+        use std::time::{Duration, Instant};
+
+        /// Wall time that can be stepped independently of monotonic time, as NTP does.
+        #[derive(Clone)]
+        struct SteppedClock {
+            start_instant: Instant,
+            timestamp: Rc<Cell<u64>>,
+            elapsed: Rc<Cell<u64>>,
+        }
+
+        impl Clock for SteppedClock {
+            fn timestamp(&self) -> u64 {
+                self.timestamp.get()
+            }
+
+            fn now(&self) -> Instant {
+                self.start_instant + Duration::from_micros(self.elapsed.get())
+            }
+        }
+
+        let clock = SteppedClock {
+            start_instant: Instant::now(),
+            timestamp: Rc::new(Cell::new(1_000_000)),
+            elapsed: Rc::new(Cell::new(0)),
+        };
+        let reporter = CapturingReporter::default();
+        let tracer = tracer::Tracer::default()
+            .with_clock(clock.clone())
+            .with_reporter(reporter.clone());
+
+        let span = tracer.start_span("stepped".to_owned(), None);
+        clock.timestamp.set(400_000);
+        clock.elapsed.set(300);
+        span.finish();
+        assert_eq!(reporter.spans.borrow()[0].duration, 300);
+
+        // Without a monotonic start the duration falls back to wall time.
+        let span = tracer
+            .span_builder("explicit")
+            .with_start_time(100_000)
+            .start();
+        span.finish();
+        assert_eq!(reporter.spans.borrow()[1].duration, 300_000);
+    }
+
+    #[test]
+    fn test_seeded_id_generator() {
+        let first = SeededIdGenerator::new(42);
+        let second = SeededIdGenerator::new(42);
+        assert_eq!(first.next_id(), second.next_id());
+
+        let tracer = tracer::Tracer::default()
+            .with_id_generator(SeededIdGenerator::new(7))
+            .with_trace_id_128bit(false);
+        let span = tracer.start_span("64-bit".to_owned(), None);
+        let trace_id = span.context.trace_id().expect("should have trace id");
+        assert_eq!(trace_id.high, 0);
+        assert_ne!(Some(trace_id.low), span.context.span_id());
+
+        let tracer = tracer.with_trace_id_128bit(true);
+        let span = tracer.start_span("128-bit".to_owned(), None);
+        let trace_id = span.context.trace_id().expect("should have trace id");
+        assert_ne!(trace_id.high, 0);
+    }
+
+    #[test]
+    fn test_child_generates_only_span_id() {
+        use std::cell::Cell;
+
+        struct SequentialIdGenerator(Cell<u64>);
+
+        impl IdGenerator for SequentialIdGenerator {
+            fn next_id(&self) -> u64 {
+                self.0.set(self.0.get() + 1);
+                self.0.get()
+            }
+        }
+
+        let ids = SequentialIdGenerator(Cell::new(0));
+        let root = SpanContext::root(&ids, true);
+        assert_eq!(root.trace_id(), Some(TraceId { low: 1, high: 2 }));
+        assert_eq!(root.span_id(), Some(3));
+
+        let child = SpanContext::child(Some(&root), &ids, true);
+        assert_eq!(child.trace_id(), root.trace_id());
+        assert_eq!(child.span_id(), Some(4));
+        assert_eq!(child.parent_span_id(), Some(3));
+        assert_eq!(ids.0.get(), 4);
+    }
+
+    #[test]
+    fn test_span_limits() {
+        let reporter = CapturingReporter::default();
+        let tracer = tracer::Tracer::default()
+            .with_reporter(reporter.clone())
+            .with_span_limits(SpanLimits {
+                max_tags: 1,
+                max_logs: 1,
+                max_value_length: 4,
+            });
+
+        let mut span = tracer.start_span("limited".to_owned(), None);
+        span.set_tag("first", "truncated");
+        span.set_tag("first", "ok");
+        span.set_tag("second", "dropped");
+        span.log_event("logged".to_owned());
+        span.log_event("dropped".to_owned());
+
+        assert_eq!(span.tags.len(), 1);
+        assert_eq!(span.tags["first"], TagValue::from("ok"));
+        assert_eq!(span.logs.len(), 1);
+        span.finish();
+
+        let reported = reporter.spans.borrow();
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].tags["jaeger.dropped_tags"], TagValue::U32(1));
+        assert_eq!(reported[0].tags["jaeger.dropped_logs"], TagValue::U32(1));
+        // The first tag value and the "logged" event were both cut to four characters.
+        assert_eq!(reported[0].tags["jaeger.truncated_values"], TagValue::U32(2));
+    }
+
+    #[test]
+    fn test_span_logger() {
+        let clock = ManualClock::new(1_000_000);
+        let reporter = CapturingReporter::default();
+        let tracer = tracer::Tracer::default()
+            .with_clock(clock.clone())
+            .with_reporter(reporter.clone())
+            .with_span_limits(SpanLimits {
+                max_logs: 1,
+                ..SpanLimits::default()
+            });
+        let logger = SpanLogger::new();
+        let record = |message| {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("{}", message))
+                    .level(Level::Info)
+                    .target("test")
+                    .line(Some(42))
+                    .build(),
+            )
+        };
+
+        record("outside");
+        let span = tracer.start_span("logging".to_owned(), None);
+        clock.advance(500);
+        span.in_scope(|| {
+            record("hello");
+            record("dropped");
+        });
+        record("after");
+        span.finish();
+
+        let reported = reporter.spans.borrow();
+        assert_eq!(reported[0].logs.len(), 1);
+        let (timestamp, ref fields) = reported[0].logs[0];
+        assert_eq!(timestamp, 1_000_500);
+        assert_eq!(fields["message"], TagValue::from("hello"));
+        assert_eq!(fields["level"], TagValue::from("INFO"));
+        assert_eq!(fields["line"], TagValue::U32(42));
+        assert_eq!(reported[0].tags["jaeger.dropped_logs"], TagValue::U32(1));
+    }
+
+    #[test]
+    fn test_incomplete_snapshot() {
+        let clock = ManualClock::new(1_000_000);
+        let reporter = CapturingReporter::default();
+        let tracer = tracer::Tracer::default()
+            .with_clock(clock.clone())
+            .with_reporter(reporter.clone());
+
+        let mut span = tracer.start_span("long-running".to_owned(), None);
+        clock.advance(500);
+        span.report_incomplete();
+
+        assert!(!span.incomplete);
+        assert_eq!(span.duration, 0);
+
+        clock.advance(500);
+        span.finish();
+
+        let reported = reporter.spans.borrow();
+        assert_eq!(reported.len(), 2);
+        assert!(reported[0].incomplete);
+        assert_eq!(reported[0].duration, 500);
+        assert!(!reported[1].incomplete);
+        assert_eq!(reported[1].duration, 1_000);
+    }
+
+    #[test]
+    fn test_processors() {
+        let reporter = CapturingReporter::default();
+        let skipped = Rc::new(RefCell::new(0));
+        let skipped_count = skipped.clone();
+        let tracer = tracer::Tracer::default()
+            .with_reporter(reporter.clone())
+            .with_processor(|span: &mut Span| {
+                span.operation_name.push_str("-first");
+                true
+            })
+            .with_processor(|span: &mut Span| {
+                span.operation_name.push_str("-second");
+                !span.operation_name.starts_with("health")
+            })
+            .with_processor(move |_span: &mut Span| {
+                *skipped_count.borrow_mut() += 1;
+                true
+            });
+
+        tracer.start_span("work".to_owned(), None).finish();
+        tracer.start_span("health".to_owned(), None).finish();
+
+        let mut span = tracer.start_span("long-running".to_owned(), None);
+        span.report_incomplete();
+        assert_eq!(span.operation_name, "long-running");
+        span.finish();
+
+        let reported = reporter.spans.borrow();
+        let names: Vec<&str> = reported
+            .iter()
+            .map(|span| span.operation_name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "work-first-second",
+                "long-running-first-second",
+                "long-running-first-second"
+            ]
+        );
+        // The dropped span never reached the last processor.
+        assert_eq!(*skipped.borrow(), 3);
+    }
+
+    #[test]
+    fn test_redactor() {
+        let reporter = CapturingReporter::default();
+        let tracer = tracer::Tracer::default()
+            .with_reporter(reporter.clone())
+            .with_processor(Redactor::new());
+
+        let mut span = tracer.start_span("signup".to_owned(), None);
+        span.set_tag("http.url", "/users?email=jane@example.com");
+        span.log(vec![(
+            "message",
+            TagValue::from("sending welcome mail to jane@example.com"),
+        )]);
+        span.finish();
+
+        let reported = reporter.spans.borrow();
+        assert_eq!(
+            reported[0].tags["http.url"],
+            TagValue::from("/users?email=REDACTED")
+        );
+        assert_eq!(
+            reported[0].logs[0].1["message"],
+            TagValue::from("sending welcome mail to REDACTED")
+        );
+    }
+
+    #[test]
+    fn test_active_span() {
+        let tracer = tracer::Tracer::default();
+
+        let outer = tracer.start_active_span("outer");
+        {
+            let inner = tracer.start_active_span("inner");
+            assert_eq!(inner.context.parent_span_id(), outer.context.span_id());
+            assert_eq!(
+                tracer.active_span_context().and_then(|context| context.span_id()),
+                inner.context.span_id()
+            );
+        }
+
+        let detached = tracer.span_builder("detached").ignore_active_span().start();
+        assert_eq!(detached.context.parent_span_id(), None);
+
+        let sibling = tracer.start_span("sibling".to_owned(), None);
+        assert_eq!(sibling.context.parent_span_id(), outer.context.span_id());
+
+        drop(outer);
+        assert!(tracer.active_span_context().is_none());
+
+        let first = tracer.start_active_span("first");
+        let second = tracer.start_active_span("second");
+        drop(first);
+        assert_eq!(
+            tracer.active_span_context().and_then(|context| context.span_id()),
+            second.context.span_id()
+        );
+        drop(second);
+        assert!(tracer.active_span_context().is_none());
+    }
+
+    #[test]
+    fn test_instrumented_future() {
+        use futures::future::{self, Future};
+
+        let reporter = CapturingReporter::default();
+        let tracer = tracer::Tracer::default().with_reporter(reporter.clone());
+
+        let future = future::lazy(|| {
+            let child = tracer.start_span("child".to_owned(), None);
+            Ok::<_, ParseIntError>(child.context.parent_span_id())
+        })
+        .in_span(&tracer, "parent");
+
+        let parent_span_id = future.span().and_then(|span| span.context.span_id());
+        assert_eq!(future.wait(), Ok(parent_span_id));
+        assert!(tracer.active_span_context().is_none());
+
+        let error = "failed".parse::<u32>().unwrap_err();
+        let failed = future::err::<(), _>(error.clone()).in_span(&tracer, "failing");
+        assert_eq!(failed.wait(), Err(error));
+        let unit_error = future::err::<(), ()>(()).in_span(&tracer, "unit error");
+        assert_eq!(unit_error.wait(), Err(()));
+
+        let reported = reporter.spans.borrow();
+        let failing = reported
+            .iter()
+            .find(|span| span.operation_name == "failing")
+            .expect("failing span should be reported");
+        assert_eq!(failing.tags["error"], TagValue::Boolean(true));
+        assert_eq!(failing.logs[0].1["event"], TagValue::from("error"));
+        assert_eq!(
+            failing.logs[0].1["error.kind"],
+            TagValue::from(::std::any::type_name::<ParseIntError>())
+        );
+        assert_eq!(
+            failing.logs[0].1["error.object"],
+            TagValue::from("ParseIntError { kind: InvalidDigit }")
+        );
+
+        let unit_error = reported
+            .iter()
+            .find(|span| span.operation_name == "unit error")
+            .expect("unit error span should be reported");
+        assert_eq!(unit_error.tags["error"], TagValue::Boolean(true));
+        assert_eq!(unit_error.logs[0].1["error.object"], TagValue::from("()"));
+    }
+
+    #[test]
+    fn test_instrumented_stream() {
+        use futures::stream;
+        use futures::{Async, Poll, Stream};
+
+        let reporter = CapturingReporter::default();
+        let tracer = tracer::Tracer::default().with_reporter(reporter.clone());
+        let span = tracer.start_span("stream".to_owned(), None);
+        let span_id = span.context.span_id();
+
+        let active = RefCell::new(Vec::new());
+        let mut remaining = 2;
+        let mut instrumented = stream::poll_fn(|| -> Poll<Option<u32>, ()> {
+            active
+                .borrow_mut()
+                .push(tracer.active_span_context().and_then(|context| context.span_id()));
+            if remaining == 0 {
+                return Ok(Async::Ready(None));
+            }
+            remaining -= 1;
+            Ok(Async::Ready(Some(remaining)))
+        })
+        .instrument(span);
+
+        assert_eq!(instrumented.poll(), Ok(Async::Ready(Some(1))));
+        assert_eq!(instrumented.poll(), Ok(Async::Ready(Some(0))));
+        assert!(reporter.spans.borrow().is_empty());
+
+        assert_eq!(instrumented.poll(), Ok(Async::Ready(None)));
+        assert!(instrumented.span().is_none());
+        drop(instrumented);
+
+        assert_eq!(*active.borrow(), vec![span_id; 3]);
+        assert!(tracer.active_span_context().is_none());
+        let reported = reporter.spans.borrow();
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].operation_name, "stream");
+    }
+
+    #[test]
+    fn test_in_scope_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let tracer = tracer::Tracer::default();
+        let span = tracer.start_span("panicking".to_owned(), None);
+
+        let result = catch_unwind(AssertUnwindSafe(|| span.in_scope(|| panic!("in scope"))));
+        assert!(result.is_err());
+        assert!(tracer.active_span_context().is_none());
+    }
+
+    #[traced(name = "parse", tags(value))]
+    fn traced_parse(tracer: &tracer::Tracer, value: &str) -> Result<u32, ParseIntError> {
+        let active = tracer.active_span_context().and_then(|context| context.span_id());
+        assert!(active.is_some());
+        value.parse()
+    }
+
+    #[test]
+    fn test_traced_attribute() {
+        let reporter = CapturingReporter::default();
+        let tracer = tracer::Tracer::default().with_reporter(reporter.clone());
+
+        assert_eq!(traced_parse(&tracer, "42"), Ok(42));
+        assert!(traced_parse(&tracer, "forty-two").is_err());
+        assert!(tracer.active_span_context().is_none());
+
+        let reported = reporter.spans.borrow();
+        assert_eq!(reported.len(), 2);
+
+        let ok = &reported[0];
+        assert_eq!(ok.operation_name, "parse");
+        assert_eq!(ok.tags["value"], TagValue::from(format!("{:?}", "42")));
+        assert!(!ok.tags.contains_key("error"));
+        assert!(ok.logs.is_empty());
+
+        let err = &reported[1];
+        assert_eq!(err.operation_name, "parse");
+        assert_eq!(
+            err.tags["value"],
+            TagValue::from(format!("{:?}", "forty-two"))
+        );
+        assert_eq!(err.tags["error"], TagValue::Boolean(true));
+        assert_eq!(err.logs.len(), 1);
+        assert_eq!(err.logs[0].1["event"], TagValue::from("error"));
+    }
+
+    #[traced(name = "digits")]
+    fn traced_digits(tracer: &tracer::Tracer, value: &str) -> impl Iterator<Item = char> {
+        assert!(tracer.active_span_context().is_some());
+        let digits: Vec<char> = value.chars().filter(char::is_ascii_digit).collect();
+        digits.into_iter()
+    }
+
+    #[test]
+    fn test_traced_impl_trait() {
+        let reporter = CapturingReporter::default();
+        let tracer = tracer::Tracer::default().with_reporter(reporter.clone());
+
+        assert_eq!(traced_digits(&tracer, "a1b2").collect::<String>(), "12");
+        assert!(tracer.active_span_context().is_none());
+
+        let reported = reporter.spans.borrow();
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].operation_name, "digits");
+    }
+
+    #[test]
+    fn test_span_observer() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        struct RecordingObserver(Rc<RefCell<Vec<String>>>);
+
+        impl SpanObserver for RecordingObserver {
+            fn on_start(&self, span: &Span) {
+                self.0
+                    .borrow_mut()
+                    .push(format!("start {}", span.operation_name));
+            }
+
+            fn on_set_operation_name(&self, _span: &Span, operation_name: &str) {
+                self.0.borrow_mut().push(format!("rename {}", operation_name));
+            }
+
+            fn on_set_tag(&self, _span: &Span, key: &str, _value: &TagValue) {
+                self.0.borrow_mut().push(format!("tag {}", key));
+            }
+
+            fn on_finish(&self, span: &Span) {
+                self.0
+                    .borrow_mut()
+                    .push(format!("finish {}", span.operation_name));
+            }
+        }
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let tracer = tracer::Tracer::default().with_observer(RecordingObserver(events.clone()));
+
+        let mut span = tracer.span_builder("first").with_tag("start", true).start();
+        span.set_operation_name("second");
+        span.set_tag("key", "value");
+        span.finish();
+
+        assert_eq!(
+            *events.borrow(),
+            vec!["start first", "rename second", "tag key", "finish second"]
+        );
+    }
+
+    #[test]
+    fn test_span_context_display() {
+        let mut context = SpanContext::new();
+        context.set_trace_id(TraceId {
+            low: 0xa2fb_4a1d_1a96_d312,
+            high: 0x4bf9_2f35_77b3_4da6,
+        });
+        context.set_span_id(0xb7ad_6b71_6920_3331);
+        context.set_sampled(true);
+
+        assert_eq!(
+            context.to_string(),
+            "4bf92f3577b34da6a2fb4a1d1a96d312:b7ad6b7169203331:0:1"
+        );
+        assert_eq!(
+            TraceIds(&context).to_string(),
+            "trace_id=4bf92f3577b34da6a2fb4a1d1a96d312 span_id=b7ad6b7169203331"
+        );
+    }
 }
@@ -0,0 +1,55 @@
+use opentracing_rust_wip::TagValue;
+
+/// Caps on how much data a single span may hold, so that instrumentation that
+/// tags or logs in a loop can't exhaust memory or exceed the UDP packet size.
+///
+/// Anything dropped or truncated is counted and reported on the span as
+/// `jaeger.dropped_tags`, `jaeger.dropped_logs` and `jaeger.truncated_values`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpanLimits {
+    /// Maximum number of distinct tags.
+    pub max_tags: usize,
+    /// Maximum number of log records.
+    pub max_logs: usize,
+    /// Maximum length in bytes of string and binary tag or log values.
+    pub max_value_length: usize,
+}
+
+impl Default for SpanLimits {
+    fn default() -> Self {
+        SpanLimits {
+            max_tags: 256,
+            max_logs: 256,
+            max_value_length: 4096,
+        }
+    }
+}
+
+impl SpanLimits {
+    /// Truncates the value to `max_value_length`, returning whether it was truncated.
+    pub fn truncate(&self, value: &mut TagValue) -> bool {
+        match *value {
+            TagValue::String(ref mut string) => truncate_string(string, self.max_value_length),
+            TagValue::Binary(ref mut binary) if binary.len() > self.max_value_length => {
+                binary.truncate(self.max_value_length);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Truncates the string to at most `max_length` bytes without splitting a
+/// character, returning whether it was truncated.
+pub fn truncate_string(value: &mut String, max_length: usize) -> bool {
+    if value.len() <= max_length {
+        return false;
+    }
+
+    let mut length = max_length;
+    while !value.is_char_boundary(length) {
+        length -= 1;
+    }
+    value.truncate(length);
+    true
+}
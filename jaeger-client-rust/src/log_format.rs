@@ -0,0 +1,100 @@
+use env_logger::fmt::Formatter;
+use log::Record;
+use opentracing_rust_wip::ScopeManager;
+use std::fmt;
+use std::io::{self, Write};
+
+use scope_manager::ThreadLocalScopeManager;
+use span::SpanContext;
+
+/// Formats the ids of a span context as `trace_id={trace-id} span_id={span-id}`,
+/// the fields `TraceContextFormat` adds to log lines, with zero for missing ids.
+///
+/// Use it to tag messages logged with a context at hand, such as the one of a
+/// request:
+///
+/// ```ignore
+/// info!("{} charging card", TraceIds(&context));
+/// ```
+pub struct TraceIds<'a>(pub &'a SpanContext);
+
+impl<'a> fmt::Display for TraceIds<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "trace_id={} span_id={:x}",
+            self.0
+                .trace_id()
+                .map_or("0".to_owned(), |trace_id| trace_id.to_string()),
+            self.0.span_id().unwrap_or(0)
+        )
+    }
+}
+
+/// An env_logger format that adds the trace and span id of the active span to
+/// each line, so log lines can be matched to traces.
+///
+/// The active span is read from `ThreadLocalScopeManager`, or from the scope
+/// manager given to `with_scope_manager` when tracers use another one.
+///
+/// ```ignore
+/// let format = TraceContextFormat::new();
+/// env_logger::Builder::from_default_env()
+///     .format(move |buf, record| format.format(buf, record))
+///     .init();
+/// ```
+///
+/// Produces lines like
+/// `[2019-06-01T12:00:00Z INFO app trace_id=4bf92f3577b34da6 span_id=a2fb4a1d1a96d312] message`.
+pub struct TraceContextFormat {
+    scope_manager: Box<ScopeManager<SpanContext> + Send + Sync>,
+}
+
+impl TraceContextFormat {
+    pub fn new() -> Self {
+        Self::with_scope_manager(ThreadLocalScopeManager)
+    }
+
+    /// Reads the active span from the given scope manager.
+    pub fn with_scope_manager<M>(scope_manager: M) -> Self
+    where
+        M: ScopeManager<SpanContext> + Send + Sync + 'static,
+    {
+        TraceContextFormat {
+            scope_manager: Box::new(scope_manager),
+        }
+    }
+
+    pub fn format(&self, buf: &mut Formatter, record: &Record) -> io::Result<()> {
+        let context = self.scope_manager.active();
+        format_with_span_context(buf, record, context.as_ref())
+    }
+}
+
+impl Default for TraceContextFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes a log line in the `TraceContextFormat` layout with the ids of the
+/// given context, if any.
+pub fn format_with_span_context(
+    buf: &mut Formatter,
+    record: &Record,
+    context: Option<&SpanContext>,
+) -> io::Result<()> {
+    let ids = context
+        .map(|context| format!(" {}", TraceIds(context)))
+        .unwrap_or_default();
+
+    writeln!(
+        buf,
+        "[{} {} {}{}] {}",
+        buf.timestamp(),
+        record.level(),
+        record.target(),
+        ids,
+        record.args()
+    )
+}
@@ -0,0 +1,21 @@
+use opentracing_rust_wip::TagValue;
+
+use span::Span;
+
+/// Receives callbacks as spans change, whether or not they are sampled.
+///
+/// This is the hook for in-process metrics and span-based assertions. Every
+/// callback has an empty default, so observers only implement what they need.
+pub trait SpanObserver {
+    /// Called once the span has started, with its operation name and start tags set.
+    fn on_start(&self, _span: &Span) {}
+
+    /// Called after the operation name has changed.
+    fn on_set_operation_name(&self, _span: &Span, _operation_name: &str) {}
+
+    /// Called after a tag has been set, with the value as stored after limits applied.
+    fn on_set_tag(&self, _span: &Span, _key: &str, _value: &TagValue) {}
+
+    /// Called when the span finishes, before it is reported.
+    fn on_finish(&self, _span: &Span) {}
+}
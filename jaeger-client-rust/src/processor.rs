@@ -0,0 +1,280 @@
+use opentracing_rust_wip::{TagValue, Tags};
+
+use span::Span;
+
+const REDACTED: &str = "REDACTED";
+
+/// Rewrites finished spans before they reach the reporter.
+///
+/// Processors run in the order they were added to the tracer, after the
+/// observers have seen the span. Returning `false` drops the span, and the
+/// remaining processors are skipped.
+///
+/// Closures taking `&mut Span` implement this trait, which covers one-off
+/// rules such as renaming operations:
+///
+/// ```ignore
+/// let tracer = Tracer::default().with_processor(|span: &mut Span| {
+///     span.operation_name = span.operation_name.replace("/v1", "");
+///     span.operation_name != "health_check"
+/// });
+/// ```
+pub trait SpanProcessor {
+    fn process(&self, span: &mut Span) -> bool;
+}
+
+impl<F> SpanProcessor for F
+where
+    F: Fn(&mut Span) -> bool,
+{
+    fn process(&self, span: &mut Span) -> bool {
+        self(span)
+    }
+}
+
+/// Removes tags by key from spans and their logs.
+///
+/// A pattern ending in `*` matches every key starting with the rest of the
+/// pattern, otherwise the key has to match exactly.
+#[derive(Clone, Debug, Default)]
+pub struct RemoveTags {
+    patterns: Vec<String>,
+}
+
+impl RemoveTags {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_pattern<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.patterns.push(pattern.into());
+        self
+    }
+
+    fn matches(&self, key: &str) -> bool {
+        self.patterns.iter().any(|pattern| {
+            if pattern.ends_with('*') {
+                key.starts_with(&pattern[..pattern.len() - 1])
+            } else {
+                key == pattern
+            }
+        })
+    }
+}
+
+impl SpanProcessor for RemoveTags {
+    fn process(&self, span: &mut Span) -> bool {
+        span.tags.retain(|key, _| !self.matches(key));
+        for (_, fields) in span.logs.iter_mut() {
+            fields.retain(|key, _| !self.matches(key));
+        }
+        true
+    }
+}
+
+/// Scrubs personal data out of string tags, by default `http.url`, `http.uri`
+/// and `db.statement`, and out of string log fields, by default `message`,
+/// `error.object` and `error.causes`.
+///
+/// Email addresses, plain or percent-encoded, are replaced with `REDACTED`,
+/// and so are the values of the query parameters given to
+/// `with_query_parameter`.
+#[derive(Clone, Debug)]
+pub struct Redactor {
+    tags: Vec<String>,
+    log_fields: Vec<String>,
+    query_parameters: Vec<String>,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Redactor {
+            tags: vec![
+                Tags::HttpUrl.as_str().to_owned(),
+                // The key the actix middleware records request URIs under.
+                "http.uri".to_owned(),
+                Tags::DbStatement.as_str().to_owned(),
+            ],
+            log_fields: vec![
+                "message".to_owned(),
+                "error.object".to_owned(),
+                "error.causes".to_owned(),
+            ],
+            query_parameters: Vec::new(),
+        }
+    }
+}
+
+impl Redactor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also scrub the tag with this key.
+    pub fn with_tag<S: Into<String>>(mut self, key: S) -> Self {
+        self.tags.push(key.into());
+        self
+    }
+
+    /// Also scrub the log field with this key.
+    pub fn with_log_field<S: Into<String>>(mut self, key: S) -> Self {
+        self.log_fields.push(key.into());
+        self
+    }
+
+    /// Redact the value of this query parameter, compared case-insensitively.
+    pub fn with_query_parameter<S: Into<String>>(mut self, name: S) -> Self {
+        self.query_parameters.push(name.into());
+        self
+    }
+
+    /// The value with email addresses and the configured query parameters redacted.
+    pub fn redact(&self, value: &str) -> String {
+        redact_emails(&self.redact_query_parameters(value))
+    }
+
+    fn redact_query_parameters(&self, value: &str) -> String {
+        let query_start = match value.find('?') {
+            Some(index) if !self.query_parameters.is_empty() => index + 1,
+            _ => return value.to_owned(),
+        };
+        let query_end = value.find('#').unwrap_or_else(|| value.len());
+        if query_end < query_start {
+            return value.to_owned();
+        }
+
+        let query = value[query_start..query_end]
+            .split('&')
+            .map(|pair| match pair.find('=') {
+                Some(index) if self.is_redacted_parameter(&pair[..index]) => {
+                    format!("{}={}", &pair[..index], REDACTED)
+                }
+                _ => pair.to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+
+        format!("{}{}{}", &value[..query_start], query, &value[query_end..])
+    }
+
+    fn is_redacted_parameter(&self, name: &str) -> bool {
+        self.query_parameters
+            .iter()
+            .any(|parameter| parameter.eq_ignore_ascii_case(name))
+    }
+}
+
+impl SpanProcessor for Redactor {
+    fn process(&self, span: &mut Span) -> bool {
+        for key in self.tags.iter() {
+            if let Some(TagValue::String(value)) = span.tags.get_mut(key) {
+                *value = self.redact(value);
+            }
+        }
+        for (_, fields) in span.logs.iter_mut() {
+            for key in self.log_fields.iter() {
+                if let Some(TagValue::String(value)) = fields.get_mut(key) {
+                    *value = self.redact(value);
+                }
+            }
+        }
+        true
+    }
+}
+
+fn is_local_part_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"._%+-".contains(&byte)
+}
+
+fn is_domain_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'.' || byte == b'-'
+}
+
+/// Replaces every `local@domain.tld`, with the `@` plain or encoded as `%40`.
+fn redact_emails(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut result = String::with_capacity(value.len());
+    let mut copied = 0;
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let separator_len = if bytes[index] == b'@' {
+            1
+        } else if bytes[index..].starts_with(b"%40") {
+            3
+        } else {
+            index += 1;
+            continue;
+        };
+
+        let mut start = index;
+        while start > copied && is_local_part_byte(bytes[start - 1]) {
+            start -= 1;
+        }
+        let domain_start = index + separator_len;
+        let mut end = domain_start;
+        while end < bytes.len() && is_domain_byte(bytes[end]) {
+            end += 1;
+        }
+        while end > domain_start && bytes[end - 1] == b'.' {
+            end -= 1;
+        }
+
+        let domain = &value[domain_start..end];
+        if start < index && domain.contains('.') && !domain.starts_with('.') {
+            result.push_str(&value[copied..start]);
+            result.push_str(REDACTED);
+            copied = end;
+            index = end;
+        } else {
+            index += separator_len;
+        }
+    }
+
+    result.push_str(&value[copied..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_emails() {
+        assert_eq!(
+            redact_emails("contact jane.doe+x@example.com, or ops@localhost"),
+            "contact REDACTED, or ops@localhost"
+        );
+        assert_eq!(
+            redact_emails("/users?email=jane%40example.co.uk&page=2"),
+            "/users?email=REDACTED&page=2"
+        );
+        assert_eq!(
+            redact_emails("SELECT * FROM users WHERE email = 'a@b.io'."),
+            "SELECT * FROM users WHERE email = 'REDACTED'."
+        );
+    }
+
+    #[test]
+    fn test_redact_query_parameters() {
+        let redactor = Redactor::new()
+            .with_query_parameter("token")
+            .with_query_parameter("access_token");
+
+        assert_eq!(
+            redactor.redact("https://example.com/cb?Token=abc&state=1&access_token=x#top"),
+            "https://example.com/cb?Token=REDACTED&state=1&access_token=REDACTED#top"
+        );
+        assert_eq!(redactor.redact("/no/query"), "/no/query");
+    }
+
+    #[test]
+    fn test_remove_tags_matches() {
+        let remove = RemoveTags::new().with_pattern("user.*").with_pattern("password");
+
+        assert!(remove.matches("user.id"));
+        assert!(remove.matches("password"));
+        assert!(!remove.matches("password_hint"));
+        assert!(!remove.matches("http.url"));
+    }
+}
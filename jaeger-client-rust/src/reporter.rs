@@ -1,4 +1,4 @@
-use opentracing_rust_wip::{Reporter, Span as OpentracingSpan, TagValue};
+use opentracing_rust_wip::{Reporter, Span as OpentracingSpan, SpanReference, TagValue};
 use span::*;
 
 use jaeger_thrift::agent::*;
@@ -229,6 +229,15 @@ fn thrift_tag_from(key: &String, value: &TagValue) -> Option<Tag> {
             Some(u32_value.clone().into()),
             None,
         )),
+        TagValue::U64(u64_value) if *u64_value > i64::max_value() as u64 => Some(Tag::new(
+            key.clone(),
+            TagType::STRING,
+            Some(u64_value.to_string()),
+            None,
+            None,
+            None,
+            None,
+        )),
         TagValue::U64(u64_value) => Some(Tag::new(
             key.clone(),
             TagType::LONG,
@@ -260,9 +269,96 @@ fn thrift_tag_from(key: &String, value: &TagValue) -> Option<Tag> {
             None,
             None,
         )),
+        TagValue::Binary(binary_value) => Some(Tag::new(
+            key.clone(),
+            TagType::BINARY,
+            None,
+            None,
+            None,
+            None,
+            Some(binary_value.clone()),
+        )),
     }
 }
 
+fn thrift_span_ref_from(reference: &SpanReference<SpanContext>, trace_id: &TraceId) -> SpanRef {
+    let ref_type = match reference {
+        SpanReference::ChildOf(_) => SpanRefType::CHILD_OF,
+        SpanReference::FollowsFrom(_) => SpanRefType::FOLLOWS_FROM,
+    };
+
+    let context = reference.context();
+    let ref_trace_id = context.trace_id().unwrap_or_else(|| trace_id.clone());
+
+    SpanRef::new(
+        ref_type,
+        ref_trace_id.low as i64,
+        ref_trace_id.high as i64,
+        context.span_id().unwrap_or(0) as i64,
+    )
+}
+
+fn thrift_span_from(span: &Span) -> JaegerThriftSpan {
+    let trace_id = span
+        .context()
+        .trace_id()
+        .unwrap_or(TraceId { low: 0, high: 0 });
+
+    let tags: Vec<Tag> = span
+        .tags
+        .iter()
+        .flat_map(|(key, value)| -> Option<Tag> { thrift_tag_from(key, value) })
+        .collect();
+
+    let logs: Vec<Log> = span
+        .logs
+        .iter()
+        .map(|(timestamp, tags)| {
+            Log::new(
+                *timestamp as i64,
+                tags.iter()
+                    .flat_map(|(key, value)| thrift_tag_from(key, value))
+                    .collect(),
+            )
+        })
+        .collect();
+
+    let references = if span.references.is_empty() {
+        span.context().parent_span_id().map(|span_id| {
+            vec![SpanRef::new(
+                SpanRefType::CHILD_OF,
+                trace_id.low as i64,
+                trace_id.high as i64,
+                span_id as i64,
+            )]
+        })
+    } else {
+        Some(
+            span.references
+                .iter()
+                // Contexts extracted from sampling-only headers have no span to refer to.
+                .filter(|reference| reference.context().span_id().is_some())
+                .map(|reference| thrift_span_ref_from(reference, &trace_id))
+                .collect(),
+        )
+    };
+
+    JaegerThriftSpan::new(
+        trace_id.low as i64,
+        trace_id.high as i64,
+        span.context().span_id().unwrap_or(0) as i64,
+        span.context().parent_span_id().unwrap_or(0) as i64,
+        span.operation_name.clone(),
+        references,
+        span.context().sampled().unwrap_or(false) as i32,
+        span.start_time as i64,
+        span.duration as i64,
+        tags,
+        logs,
+        Some(span.incomplete),
+    )
+}
+
 impl<'a> Reporter<'a> for RemoteReporter {
     type Span = Span;
 
@@ -276,53 +372,7 @@ impl<'a> Reporter<'a> for RemoteReporter {
 
         trace!("Reporting span: {:?}", span.context());            
 
-        let trace_id = span
-            .context()
-            .trace_id()
-            .unwrap_or(TraceId { low: 0, high: 0 });
-
-        let tags: Vec<Tag> = span
-            .tags
-            .iter()
-            .flat_map(|(key, value)| -> Option<Tag> { thrift_tag_from(key, value) })
-            .collect();
-
-        let logs: Vec<Log> = span
-            .logs
-            .iter()
-            .map(|(timestamp, tags)| {
-                Log::new(
-                    *timestamp as i64,
-                    tags.iter()
-                        .flat_map(|(key, value)| thrift_tag_from(key, value))
-                        .collect(),
-                )
-            })
-            .collect();
-
-        let span = JaegerThriftSpan::new(
-            trace_id.low as i64,
-            trace_id.high as i64,
-            span.context().span_id().unwrap_or(0) as i64,
-            span.context().parent_span_id().unwrap_or(0) as i64,
-            span.operation_name.clone(),
-            span.context().parent_span_id().map(|span_id| {
-                vec![SpanRef::new(
-                    SpanRefType::CHILD_OF,
-                    trace_id.low as i64,
-                    trace_id.high as i64,
-                    span_id as i64,
-                )]
-            }),
-            span.context().sampled().unwrap_or(false) as i32,
-            span.start_time as i64,
-            span.duration as i64,
-            tags,
-            logs,
-            Some(false),
-        );
-
-        let batch = Batch::new(self.process.clone(), vec![span]);
+        let batch = Batch::new(self.process.clone(), vec![thrift_span_from(span)]);
 
         match self.client.borrow_mut().emit_batch(batch) {
             Ok(_) => trace!("Batch sent."),
@@ -330,3 +380,62 @@ impl<'a> Reporter<'a> for RemoteReporter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentracing_rust_wip::{StartSpanOptions, Tracer as OpentracingTracer};
+    use tracer::Tracer;
+
+    #[test]
+    fn test_u64_tag_boundary() {
+        let key = "count".to_owned();
+        let largest_long = i64::max_value() as u64;
+
+        let tag = thrift_tag_from(&key, &TagValue::U64(largest_long)).unwrap();
+        assert_eq!(tag.v_type, TagType::LONG);
+        assert_eq!(tag.v_long, Some(i64::max_value()));
+        assert_eq!(tag.v_str, None);
+
+        let tag = thrift_tag_from(&key, &TagValue::U64(largest_long + 1)).unwrap();
+        assert_eq!(tag.v_type, TagType::STRING);
+        assert_eq!(tag.v_str, Some("9223372036854775808".to_owned()));
+        assert_eq!(tag.v_long, None);
+
+        let tag = thrift_tag_from(&key, &TagValue::U64(u64::max_value())).unwrap();
+        assert_eq!(tag.v_str, Some(u64::max_value().to_string()));
+    }
+
+    #[test]
+    fn test_span_references() {
+        let tracer = Tracer::default();
+        let first = tracer.start_span("first".to_owned(), None);
+        let second = tracer.start_span("second".to_owned(), None);
+        let previous = tracer.start_span("previous".to_owned(), None);
+
+        let options = StartSpanOptions {
+            references: vec![
+                SpanReference::ChildOf(first.context.clone()),
+                SpanReference::ChildOf(second.context.clone()),
+                SpanReference::FollowsFrom(previous.context.clone()),
+            ],
+            ..StartSpanOptions::default()
+        };
+        let span = tracer.start_span_with_options("joined".to_owned(), options);
+
+        let references = thrift_span_from(&span)
+            .references
+            .expect("references should be reported");
+        assert_eq!(references.len(), 3);
+
+        let referenced = [
+            (SpanRefType::CHILD_OF, &first),
+            (SpanRefType::CHILD_OF, &second),
+            (SpanRefType::FOLLOWS_FROM, &previous),
+        ];
+        for (reference, &(ref_type, span)) in references.iter().zip(referenced.iter()) {
+            assert_eq!(reference.ref_type, ref_type);
+            assert_eq!(Some(reference.span_id as u64), span.context.span_id());
+        }
+    }
+}
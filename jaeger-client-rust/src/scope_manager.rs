@@ -0,0 +1,39 @@
+use opentracing_rust_wip::ScopeManager;
+use std::cell::RefCell;
+
+use span::SpanContext;
+
+thread_local! {
+    static ACTIVE_CONTEXTS: RefCell<Vec<SpanContext>> = RefCell::new(Vec::new());
+}
+
+/// Tracks the active span context per thread.
+///
+/// Every instance shares the same per-thread stack, so a span activated through
+/// one tracer is the implicit parent for spans started through any other tracer
+/// on the same thread.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct ThreadLocalScopeManager;
+
+impl ScopeManager<SpanContext> for ThreadLocalScopeManager {
+    fn activate(&self, context: SpanContext) {
+        ACTIVE_CONTEXTS.with(|contexts| contexts.borrow_mut().push(context));
+    }
+
+    fn deactivate(&self, context: &SpanContext) {
+        let (trace_id, span_id) = (context.trace_id(), context.span_id());
+        ACTIVE_CONTEXTS.with(|contexts| {
+            let mut contexts = contexts.borrow_mut();
+            let position = contexts
+                .iter()
+                .rposition(|active| active.span_id() == span_id && active.trace_id() == trace_id);
+            if let Some(position) = position {
+                contexts.remove(position);
+            }
+        });
+    }
+
+    fn active(&self) -> Option<SpanContext> {
+        ACTIVE_CONTEXTS.with(|contexts| contexts.borrow().last().cloned())
+    }
+}
@@ -1,15 +1,25 @@
 use opentracing_api::SpanContext as OpentracingSpanContext;
-use opentracing_rust_wip::{FinishedSpan, Reporter, Span as OpentracingSpan, TagValue};
+use opentracing_rust_wip::{
+    FinishedSpan, Reporter, ScopeManager, Span as OpentracingSpan, SpanReference,
+    StartSpanOptions, TagValue,
+};
 
-use rand::random;
 use std::boxed::Box;
 use std::collections::hash_map::Iter as HashMapIter;
 use std::collections::HashMap;
+use std::fmt;
 use std::num::ParseIntError;
 use std::rc::{Rc, Weak};
+use std::time::Instant;
 use tracer::Tracer;
 
-use reporter::RemoteReporter;
+use baggage::BaggageRestrictionManager;
+use clock::{duration_to_micros, Clock};
+use id_generator::{IdGenerator, RandomIdGenerator};
+use limits::{truncate_string, SpanLimits};
+use observer::SpanObserver;
+use processor::SpanProcessor;
+use span_logger::LogBuffer;
 use std::convert::TryFrom;
 
 #[derive(Default, Debug, Clone)]
@@ -26,7 +36,7 @@ pub fn convert_hex_to_u64(value: &str) -> Result<u64, ParseIntError> {
 
 pub type SpanId = u64;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TraceId {
     pub low: u64,
     pub high: u64,
@@ -42,6 +52,12 @@ impl TraceId {
     }
 }
 
+impl fmt::Display for TraceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_hex_string())
+    }
+}
+
 #[derive(Debug)]
 pub enum TraceIdParseError {
     ParseIntError(ParseIntError),
@@ -75,18 +91,27 @@ impl TryFrom<&String> for TraceId {
 }
 
 impl SpanContext {
+    /// Creates the context of a new root span with a random 64-bit trace id.
     pub fn new() -> Self {
+        Self::root(&RandomIdGenerator, false)
+    }
+
+    /// Creates the context of a new root span, with a 128-bit trace id if requested.
+    pub fn root(id_generator: &IdGenerator, trace_id_128bit: bool) -> Self {
         let baggage = HashMap::new();
         let mut span_context = SpanContext {
             baggage: Box::from(baggage),
         };
-        let new_id = Self::generate_id();
 
         span_context.set_trace_id(TraceId {
-            low: new_id,
-            high: Self::generate_id(),
+            low: id_generator.next_id(),
+            high: if trace_id_128bit {
+                id_generator.next_id()
+            } else {
+                0
+            },
         });
-        span_context.set_span_id(new_id);
+        span_context.set_span_id(id_generator.next_id());
         span_context
     }
 
@@ -135,14 +160,22 @@ impl SpanContext {
             .insert("x-b3-sampled".into(), format!("{:x}", value as i32));
     }
 
-    pub fn child(parent: Option<&SpanContext>) -> Self {
-        let mut child = Self::new();
-
-        if let Some(parent) = parent {
-            if let Some(trace_id) = parent.trace_id() {
+    pub fn child(
+        parent: Option<&SpanContext>,
+        id_generator: &IdGenerator,
+        trace_id_128bit: bool,
+    ) -> Self {
+        let mut child = match parent.and_then(SpanContext::trace_id) {
+            Some(trace_id) => {
+                let mut child = SpanContext::default();
                 child.set_trace_id(trace_id);
+                child.set_span_id(id_generator.next_id());
+                child
             }
+            None => Self::root(id_generator, trace_id_128bit),
+        };
 
+        if let Some(parent) = parent {
             if let Some(parent_span_id) = parent.span_id() {
                 child.set_parent_span_id(parent_span_id);
             }
@@ -158,10 +191,20 @@ impl SpanContext {
     pub fn set(&mut self, name: String, value: String) -> Option<String> {
         self.baggage.insert(name, value)
     }
+}
 
-    fn generate_id() -> u64 {
-        // TODO: Fix issue with generated IDs that are too large
-        random::<u64>()
+/// Formats the context like Jaeger's `uber-trace-id` header:
+/// `{trace-id}:{span-id}:{parent-span-id}:{flags}`, with zero for missing ids.
+impl fmt::Display for SpanContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{:x}:{:x}:{:x}",
+            self.trace_id().map_or("0".to_owned(), |trace_id| trace_id.to_string()),
+            self.span_id().unwrap_or(0),
+            self.parent_span_id().unwrap_or(0),
+            self.sampled().unwrap_or(false) as u8
+        )
     }
 }
 
@@ -181,37 +224,275 @@ impl<'a> OpentracingSpanContext<'a> for SpanContext {
     }
 }
 
-#[derive(Default, Clone)]
 pub struct Span {
     pub context: SpanContext,
     pub operation_name: String,
+    pub references: Vec<SpanReference<SpanContext>>,
     pub tags: HashMap<String, TagValue>,
     pub logs: Vec<(u64, HashMap<String, TagValue>)>,
     pub start_time: u64,
     pub duration: u64,
-    reporter: Weak<RemoteReporter>,
+    /// Set while an in-progress snapshot of the span is being reported.
+    pub incomplete: bool,
+    /// Monotonic start used to measure the duration, unless an explicit start time was given.
+    start_instant: Option<Instant>,
+    reporter: Weak<Reporter<'static, Span = Span>>,
+    baggage_restriction_manager: Rc<BaggageRestrictionManager>,
+    clock: Rc<Clock>,
+    scope_manager: Rc<ScopeManager<SpanContext>>,
+    observers: Rc<Vec<Rc<SpanObserver>>>,
+    processors: Rc<Vec<Rc<SpanProcessor>>>,
+    log_buffer: Rc<LogBuffer>,
+    limits: SpanLimits,
+    dropped_tags: u32,
+    dropped_logs: u32,
+    truncated_values: u32,
 }
 
 impl<'a> Span {
-    pub fn new(start_time: u64, reporter: &Rc<RemoteReporter>) -> Span {
-        Self::child(None, start_time, reporter)
+    pub fn new(start_time: u64, tracer: &Tracer) -> Span {
+        Self::child(None, start_time, tracer)
     }
 
-    pub fn child(
-        parent: Option<&SpanContext>,
-        start_time: u64,
-        reporter: &Rc<RemoteReporter>,
+    pub fn child(parent: Option<&SpanContext>, start_time: u64, tracer: &Tracer) -> Span {
+        let options = StartSpanOptions {
+            references: parent
+                .map(|parent| vec![SpanReference::ChildOf(parent.clone())])
+                .unwrap_or_default(),
+            start_time: Some(start_time),
+            ..StartSpanOptions::default()
+        };
+
+        Self::with_options(options, tracer)
+    }
+
+    pub fn with_options(options: StartSpanOptions<SpanContext>, tracer: &Tracer) -> Span {
+        Self::with_operation_name(String::new(), options, tracer)
+    }
+
+    pub fn with_operation_name(
+        operation_name: String,
+        options: StartSpanOptions<SpanContext>,
+        tracer: &Tracer,
     ) -> Span {
-        Span {
-            context: SpanContext::child(parent),
-            operation_name: String::new(),
+        let context = SpanContext::child(
+            options.parent(),
+            &**tracer.id_generator(),
+            tracer.trace_id_128bit(),
+        );
+
+        Self::with_context(context, operation_name, options, tracer)
+    }
+
+    /// Starts a span with a context that was allocated beforehand, for spans
+    /// whose ids had to be known before the span itself could be created.
+    pub fn with_context(
+        context: SpanContext,
+        operation_name: String,
+        options: StartSpanOptions<SpanContext>,
+        tracer: &Tracer,
+    ) -> Span {
+        let clock = tracer.clock().clone();
+        let tags = options.tags.clone();
+
+        let mut span = Span {
+            context,
+            operation_name,
+            references: options.references,
             tags: HashMap::new(),
             logs: Vec::new(),
-            start_time,
+            start_time: options.start_time.unwrap_or_else(|| clock.timestamp()),
             duration: 0,
-            reporter: Rc::downgrade(reporter),
+            incomplete: false,
+            start_instant: match options.start_time {
+                Some(_) => None,
+                None => Some(clock.now()),
+            },
+            reporter: Rc::downgrade(tracer.reporter()),
+            baggage_restriction_manager: tracer.baggage_restriction_manager().clone(),
+            clock,
+            scope_manager: tracer.shared_scope_manager().clone(),
+            observers: tracer.observers().clone(),
+            processors: tracer.processors().clone(),
+            log_buffer: Rc::new(LogBuffer::new(tracer.clock().clone(), tracer.span_limits())),
+            limits: tracer.span_limits(),
+            dropped_tags: 0,
+            dropped_logs: 0,
+            truncated_values: 0,
+        };
+
+        for (key, value) in tags {
+            span.insert_tag(key, value);
+        }
+
+        if let Some(span_id) = span.context.span_id() {
+            LogBuffer::register(&span.log_buffer, span_id);
+        }
+
+        for observer in span.observers.iter() {
+            observer.on_start(&span);
+        }
+
+        span
+    }
+}
+
+impl Span {
+    /// Runs the closure with this span as the active span.
+    pub fn in_scope<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        self.scope_manager.activate(self.context.clone());
+        let _activation = Activation {
+            scope_manager: &*self.scope_manager,
+            context: &self.context,
+        };
+        f()
+    }
+
+    /// Reports a snapshot of the span as it stands, marked `incomplete`, so
+    /// that long-running work shows up before it finishes or if it never does.
+    ///
+    /// The span can be snapshotted any number of times; finishing it reports
+    /// the final, complete span. Processors run on a copy of the span, so
+    /// they can't change or drop what is reported when it finishes.
+    ///
+    /// Nothing calls this on its own: the span can't leave its thread, so the
+    /// code doing the work has to take snapshots when it sees fit, such as
+    /// every few batches of a long-running loop:
+    ///
+    /// ```ignore
+    /// for (index, batch) in batches.enumerate() {
+    ///     process(batch);
+    ///     if index % 100 == 99 {
+    ///         span.report_incomplete();
+    ///     }
+    /// }
+    /// ```
+    pub fn report_incomplete(&mut self) {
+        let reporter = match self.reporter.upgrade() {
+            Some(reporter) => reporter,
+            None => return,
+        };
+
+        self.collect_pending_logs();
+        let timestamp = self.clock.timestamp();
+        let mut snapshot = self.snapshot();
+        snapshot.duration = self.elapsed(timestamp);
+        snapshot.incomplete = true;
+        if snapshot.process() {
+            reporter.report(&snapshot);
+        }
+    }
+
+    /// A copy of the span as it stands, sharing its reporter and log buffer.
+    fn snapshot(&self) -> Span {
+        Span {
+            context: self.context.clone(),
+            operation_name: self.operation_name.clone(),
+            references: self.references.clone(),
+            tags: self.tags.clone(),
+            logs: self.logs.clone(),
+            start_time: self.start_time,
+            duration: self.duration,
+            incomplete: self.incomplete,
+            start_instant: self.start_instant,
+            reporter: self.reporter.clone(),
+            baggage_restriction_manager: self.baggage_restriction_manager.clone(),
+            clock: self.clock.clone(),
+            scope_manager: self.scope_manager.clone(),
+            observers: self.observers.clone(),
+            processors: self.processors.clone(),
+            log_buffer: self.log_buffer.clone(),
+            limits: self.limits,
+            dropped_tags: self.dropped_tags,
+            dropped_logs: self.dropped_logs,
+            truncated_values: self.truncated_values,
+        }
+    }
+
+    /// Stores the tag within the span limits, returning whether it was stored.
+    fn insert_tag(&mut self, key: String, value: TagValue) -> bool {
+        if self.tags.len() >= self.limits.max_tags && !self.tags.contains_key(&key) {
+            self.dropped_tags += 1;
+            return false;
+        }
+
+        let mut value = value;
+        if self.limits.truncate(&mut value) {
+            self.truncated_values += 1;
+        }
+        self.tags.insert(key, value);
+        true
+    }
+
+    /// Runs the tracer's processors, returning whether the span should still be reported.
+    fn process(&mut self) -> bool {
+        let processors = self.processors.clone();
+        processors.iter().all(|processor| processor.process(self))
+    }
+
+    /// Time since the span started, measured monotonically when possible.
+    fn elapsed(&self, timestamp: u64) -> u64 {
+        match self.start_instant {
+            Some(start_instant) => {
+                duration_to_micros(self.clock.now().duration_since(start_instant))
+            }
+            None => timestamp.saturating_sub(self.start_time),
+        }
+    }
+
+    /// Adds the records a `SpanLogger` collected while this span was active.
+    fn collect_pending_logs(&mut self) {
+        let (logs, dropped_logs, truncated_values) = self.log_buffer.take();
+        self.dropped_logs += dropped_logs;
+        self.truncated_values += truncated_values;
+        for (timestamp, fields) in logs {
+            self.log_at(timestamp, fields);
         }
     }
+
+    fn report(mut self) -> FinishedSpan<SpanContext> {
+        if let Some(span_id) = self.context.span_id() {
+            LogBuffer::unregister(span_id);
+        }
+        self.collect_pending_logs();
+
+        for observer in self.observers.iter() {
+            observer.on_finish(&self);
+        }
+
+        let dropped_counts = [
+            ("jaeger.dropped_tags", self.dropped_tags),
+            ("jaeger.dropped_logs", self.dropped_logs),
+            ("jaeger.truncated_values", self.truncated_values),
+        ];
+        for (key, count) in dropped_counts.iter() {
+            if *count > 0 {
+                self.tags.insert((*key).to_owned(), TagValue::U32(*count));
+            }
+        }
+
+        if let Some(reporter) = self.reporter.upgrade() {
+            if self.process() {
+                reporter.report(&self);
+            }
+        }
+        FinishedSpan::new(self.context)
+    }
+}
+
+/// Deactivates the context when dropped, so that a panic can't leave it active.
+struct Activation<'s> {
+    scope_manager: &'s ScopeManager<SpanContext>,
+    context: &'s SpanContext,
+}
+
+impl<'s> Drop for Activation<'s> {
+    fn drop(&mut self) {
+        self.scope_manager.deactivate(self.context);
+    }
 }
 
 impl<'a> OpentracingSpan<'a> for Span {
@@ -221,11 +502,19 @@ impl<'a> OpentracingSpan<'a> for Span {
         &self.context
     }
 
-    fn set_tag<S>(&mut self, key: S, value: TagValue)
+    fn set_tag<S, V>(&mut self, key: S, value: V)
     where
         S: Into<String>,
+        V: Into<TagValue>,
     {
-        self.tags.insert(key.into(), value);
+        let key = key.into();
+        if !self.insert_tag(key.clone(), value.into()) {
+            return;
+        }
+
+        for observer in self.observers.iter() {
+            observer.on_set_tag(self, &key, &self.tags[&key]);
+        }
     }
 
     fn unset_tag<S>(&mut self, key: S)
@@ -251,7 +540,8 @@ impl<'a> OpentracingSpan<'a> for Span {
         S: Into<String>,
         I: IntoIterator<Item = (S, TagValue)>,
     {
-        self.log_at(Tracer::timestamp(), tags)
+        let timestamp = self.clock.timestamp();
+        self.log_at(timestamp, tags)
     }
 
     fn log_at<S, I>(&mut self, timestamp: u64, tags: I)
@@ -259,19 +549,61 @@ impl<'a> OpentracingSpan<'a> for Span {
         S: Into<String>,
         I: IntoIterator<Item = (S, TagValue)>,
     {
-        self.logs.push((
-            timestamp,
-            tags.into_iter()
-                .map(|(key, value)| (key.into(), value))
-                .collect(),
-        ))
+        if self.logs.len() >= self.limits.max_logs {
+            self.dropped_logs += 1;
+            return;
+        }
+
+        let limits = self.limits;
+        let mut truncated_values = 0;
+        let fields: HashMap<String, TagValue> = tags
+            .into_iter()
+            .map(|(key, mut value)| {
+                if limits.truncate(&mut value) {
+                    truncated_values += 1;
+                }
+                (key.into(), value)
+            })
+            .collect();
+
+        self.truncated_values += truncated_values;
+        self.logs.push((timestamp, fields))
     }
 
     fn set_baggage_item<S>(&mut self, key: S, value: String)
     where
         S: Into<String>,
     {
-        self.context.baggage.insert(key.into(), value);
+        let key = key.into();
+        let mut value = value;
+        let restriction = self.baggage_restriction_manager.get_restriction(&key);
+
+        if !restriction.key_allowed {
+            self.log(vec![
+                ("event", TagValue::String("baggage".to_owned())),
+                ("key", TagValue::String(key)),
+                ("value", TagValue::String(value)),
+                ("invalid", TagValue::Boolean(true)),
+            ]);
+            return;
+        }
+
+        let truncated = truncate_string(&mut value, restriction.max_value_length);
+
+        let mut fields = vec![
+            ("event", TagValue::String("baggage".to_owned())),
+            ("key", TagValue::String(key.clone())),
+            ("value", TagValue::String(value.clone())),
+        ];
+        if truncated {
+            fields.push(("truncated", TagValue::Boolean(true)));
+        }
+        if self.context.baggage.contains_key(&key) {
+            fields.push(("override", TagValue::Boolean(true)));
+        }
+        self.log(fields);
+
+        self.context.baggage.insert(key, value);
     }
 
     fn unset_baggage_item<S>(&mut self, key: S)
@@ -292,25 +624,27 @@ impl<'a> OpentracingSpan<'a> for Span {
     where
         S: Into<String>,
     {
-        self.operation_name = name.into()
+        self.operation_name = name.into();
+
+        for observer in self.observers.iter() {
+            observer.on_set_operation_name(self, &self.operation_name);
+        }
     }
 
     fn operation_name(&self) -> &String {
         &self.operation_name
     }
 
-    fn finish(&self) -> FinishedSpan<SpanContext> {
-        self.finish_at(Tracer::timestamp())
+    fn finish(mut self) -> FinishedSpan<SpanContext> {
+        let timestamp = self.clock.timestamp();
+        self.duration = self.elapsed(timestamp);
+        self.report()
     }
 
-    fn finish_at(&self, timestamp: u64) -> FinishedSpan<SpanContext> {
-        if let Some(reporter) = self.reporter.upgrade() {
-            let mut span_to_report = self.clone();
-            if (timestamp > span_to_report.start_time) {
-                span_to_report.duration = timestamp - span_to_report.start_time;
-            }
-            reporter.report(&span_to_report);
+    fn finish_at(mut self, timestamp: u64) -> FinishedSpan<SpanContext> {
+        if timestamp > self.start_time {
+            self.duration = timestamp - self.start_time;
         }
-        FinishedSpan::new(self.context.clone())
+        self.report()
     }
 }
@@ -0,0 +1,220 @@
+use log::{self, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use opentracing_rust_wip::{ScopeManager, TagValue};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use clock::Clock;
+use limits::SpanLimits;
+use scope_manager::ThreadLocalScopeManager;
+use span::{SpanContext, SpanId};
+
+type SpanLog = (u64, HashMap<String, TagValue>);
+
+thread_local! {
+    static LOG_BUFFERS: RefCell<HashMap<SpanId, Weak<LogBuffer>>> = RefCell::new(HashMap::new());
+}
+
+/// Log records a `SpanLogger` collected for a span, until the span takes them.
+///
+/// The buffer belongs to the span: it timestamps records with the span's clock
+/// and holds no more than the span's limits allow.
+pub struct LogBuffer {
+    clock: Rc<Clock>,
+    limits: SpanLimits,
+    logs: RefCell<Vec<SpanLog>>,
+    dropped_logs: Cell<u32>,
+    truncated_values: Cell<u32>,
+}
+
+impl LogBuffer {
+    pub fn new(clock: Rc<Clock>, limits: SpanLimits) -> Self {
+        LogBuffer {
+            clock,
+            limits,
+            logs: RefCell::new(Vec::new()),
+            dropped_logs: Cell::new(0),
+            truncated_values: Cell::new(0),
+        }
+    }
+
+    fn push(&self, fields: HashMap<String, TagValue>) {
+        let mut logs = self.logs.borrow_mut();
+        if logs.len() >= self.limits.max_logs {
+            self.dropped_logs.set(self.dropped_logs.get() + 1);
+            return;
+        }
+
+        let mut truncated_values = 0;
+        let fields = fields
+            .into_iter()
+            .map(|(key, mut value)| {
+                if self.limits.truncate(&mut value) {
+                    truncated_values += 1;
+                }
+                (key, value)
+            })
+            .collect();
+
+        self.truncated_values
+            .set(self.truncated_values.get() + truncated_values);
+        logs.push((self.clock.timestamp(), fields));
+    }
+
+    /// Removes the collected records, along with the number of records dropped
+    /// and values truncated since the last call.
+    pub fn take(&self) -> (Vec<SpanLog>, u32, u32) {
+        (
+            self.logs.replace(Vec::new()),
+            self.dropped_logs.replace(0),
+            self.truncated_values.replace(0),
+        )
+    }
+
+    /// Makes the buffer receive the records logged on this thread while the
+    /// span with the given id is active.
+    pub fn register(buffer: &Rc<LogBuffer>, span_id: SpanId) {
+        LOG_BUFFERS.with(|buffers| {
+            let mut buffers = buffers.borrow_mut();
+            // Spans dropped without being finished leave dead entries behind.
+            // Clearing them out before the map would grow keeps it bounded by
+            // the number of live spans.
+            if buffers.len() == buffers.capacity() {
+                buffers.retain(|_, buffer| buffer.upgrade().is_some());
+            }
+            buffers.insert(span_id, Rc::downgrade(buffer));
+        });
+    }
+
+    pub fn unregister(span_id: SpanId) {
+        LOG_BUFFERS.with(|buffers| buffers.borrow_mut().remove(&span_id));
+    }
+
+    fn find(span_id: SpanId) -> Option<Rc<LogBuffer>> {
+        LOG_BUFFERS.with(|buffers| {
+            buffers
+                .borrow()
+                .get(&span_id)
+                .and_then(|buffer| buffer.upgrade())
+        })
+    }
+}
+
+/// A `log::Log` that records each log record on the active span, optionally
+/// passing it on to another logger such as env_logger.
+///
+/// The active span is read from `ThreadLocalScopeManager`, which tracers use
+/// unless given another scope manager, in which case pass that one to
+/// `with_scope_manager`. Records are added to the span's logs, within its limits, when the span is
+/// reported.
+///
+/// ```ignore
+/// SpanLogger::wrapping(env_logger::Builder::from_default_env().build())
+///     .init(LevelFilter::Info)
+///     .expect("logger should only be set once");
+/// ```
+pub struct SpanLogger {
+    inner: Option<Box<Log>>,
+    scope_manager: Box<ScopeManager<SpanContext> + Send + Sync>,
+}
+
+impl SpanLogger {
+    /// Records log records on spans only.
+    pub fn new() -> Self {
+        SpanLogger {
+            inner: None,
+            scope_manager: Box::new(ThreadLocalScopeManager),
+        }
+    }
+
+    /// Records log records on spans and passes them on to the inner logger.
+    pub fn wrapping<L>(inner: L) -> Self
+    where
+        L: Log + 'static,
+    {
+        SpanLogger {
+            inner: Some(Box::new(inner)),
+            ..Self::new()
+        }
+    }
+
+    /// Reads the active span from the given scope manager.
+    pub fn with_scope_manager<M>(mut self, scope_manager: M) -> Self
+    where
+        M: ScopeManager<SpanContext> + Send + Sync + 'static,
+    {
+        self.scope_manager = Box::new(scope_manager);
+        self
+    }
+
+    /// Installs this as the global logger.
+    pub fn init(self, max_level: LevelFilter) -> Result<(), SetLoggerError> {
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+}
+
+impl Default for SpanLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Log for SpanLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner
+            .as_ref()
+            .map(|inner| inner.enabled(metadata))
+            .unwrap_or(true)
+    }
+
+    fn log(&self, record: &Record) {
+        if let Some(ref inner) = self.inner {
+            inner.log(record);
+        }
+
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let buffer = match self
+            .scope_manager
+            .active()
+            .and_then(|context| context.span_id())
+            .and_then(LogBuffer::find)
+        {
+            Some(buffer) => buffer,
+            None => return,
+        };
+
+        let mut fields = HashMap::new();
+        fields.insert("event".to_owned(), TagValue::String("log".to_owned()));
+        fields.insert(
+            "level".to_owned(),
+            TagValue::String(record.level().to_string()),
+        );
+        fields.insert(
+            "target".to_owned(),
+            TagValue::String(record.target().to_owned()),
+        );
+        fields.insert(
+            "message".to_owned(),
+            TagValue::String(record.args().to_string()),
+        );
+        if let Some(file) = record.file() {
+            fields.insert("file".to_owned(), TagValue::String(file.to_owned()));
+        }
+        if let Some(line) = record.line() {
+            fields.insert("line".to_owned(), TagValue::U32(line));
+        }
+
+        buffer.push(fields);
+    }
+
+    fn flush(&self) {
+        if let Some(ref inner) = self.inner {
+            inner.flush();
+        }
+    }
+}
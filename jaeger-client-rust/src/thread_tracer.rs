@@ -0,0 +1,71 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tracer::Tracer;
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// Tracers by owner, `None` while the owner's factory is running.
+    static TRACERS: RefCell<HashMap<usize, Option<Rc<Tracer>>>> = RefCell::new(HashMap::new());
+}
+
+/// A tracer per thread, created by a factory on first use.
+///
+/// Jaeger tracers can't be shared between threads, so layers that are
+/// installed globally hold one of these instead. Every `ThreadTracer` has
+/// its own tracers, so two of them never report through each other's tracer.
+pub struct ThreadTracer {
+    id: usize,
+    factory: Arc<Fn() -> Tracer + Send + Sync>,
+}
+
+impl ThreadTracer {
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: Fn() -> Tracer + Send + Sync + 'static,
+    {
+        ThreadTracer {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            factory: Arc::new(factory),
+        }
+    }
+
+    /// Identifies this instance, and so the tracers it creates.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Runs the closure with this thread's tracer, creating it if needed.
+    ///
+    /// Returns `None` when called again from within the factory, which
+    /// happens when creating a tracer emits a `tracing` event.
+    pub fn with<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&Tracer) -> R,
+    {
+        let tracer = match TRACERS.with(|tracers| tracers.borrow().get(&self.id).cloned()) {
+            Some(Some(tracer)) => tracer,
+            Some(None) => return None,
+            None => {
+                TRACERS.with(|tracers| tracers.borrow_mut().insert(self.id, None));
+                let tracer = Rc::new((self.factory)());
+                TRACERS.with(|tracers| tracers.borrow_mut().insert(self.id, Some(tracer.clone())));
+                tracer
+            }
+        };
+
+        Some(f(&tracer))
+    }
+}
+
+impl Drop for ThreadTracer {
+    fn drop(&mut self) {
+        // Tracers on other threads stay until those threads exit.
+        let id = self.id;
+        let _ = TRACERS.try_with(|tracers| tracers.borrow_mut().remove(&id));
+    }
+}
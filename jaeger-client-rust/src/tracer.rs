@@ -1,18 +1,67 @@
-use opentracing_rust_wip::{Reporter, Tracer as OpentracingTracer};
-use std::collections::HashMap;
+use opentracing_rust_wip::{
+    Reporter, ScopeManager, SpanReference, StartSpanOptions, TextMapReader, TextMapWriter,
+    Tracer as OpentracingTracer,
+};
+use std::env;
+use std::error::Error as StdError;
+use std::fmt;
 use std::rc::Rc;
 
-use std::time::{SystemTime, UNIX_EPOCH};
-
+use baggage::{BaggageRestrictionManager, DefaultBaggageRestrictionManager};
+use clock::{Clock, SystemClock};
+use id_generator::{IdGenerator, RandomIdGenerator};
+use limits::SpanLimits;
+use observer::SpanObserver;
+use processor::SpanProcessor;
+use scope_manager::ThreadLocalScopeManager;
+use ExtractError;
 use Extractor;
 use Injector;
 use RemoteReporter;
 use Span;
 use SpanContext;
 
+#[derive(Debug)]
 pub enum Error {
     NoExtractorFound,
-    UnableToExtract,
+    UnableToExtract(ExtractError),
+}
+
+impl From<ExtractError> for Error {
+    fn from(error: ExtractError) -> Self {
+        Error::UnableToExtract(error)
+    }
+}
+
+impl StdError for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::NoExtractorFound => "No extractor found",
+            Error::UnableToExtract(_) => "Unable to extract span context",
+        }
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        match *self {
+            Error::NoExtractorFound => None,
+            Error::UnableToExtract(ref error) => Some(error),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::NoExtractorFound => write!(f, "{}", self.description()),
+            Error::UnableToExtract(ref error) => write!(f, "{}: {}", self.description(), error),
+        }
+    }
+}
+
+/// Whether a boolean environment variable is switched on, as `1` or `true` in any case.
+fn is_enabled(value: &str) -> bool {
+    let value = value.trim();
+    value == "1" || value.eq_ignore_ascii_case("true")
 }
 
 pub enum Codec {
@@ -20,34 +69,156 @@ pub enum Codec {
 }
 
 pub struct Tracer {
-    reporter: Rc<RemoteReporter>,
+    reporter: Rc<Reporter<'static, Span = Span>>,
     codec: Codec,
+    baggage_restriction_manager: Rc<BaggageRestrictionManager>,
+    clock: Rc<Clock>,
+    id_generator: Rc<IdGenerator>,
+    trace_id_128bit: bool,
+    span_limits: SpanLimits,
+    scope_manager: Rc<ScopeManager<SpanContext>>,
+    observers: Rc<Vec<Rc<SpanObserver>>>,
+    processors: Rc<Vec<Rc<SpanProcessor>>>,
 }
 
-const NANOS_PER_MICRO: u64 = 1000;
-const MICROS_PER_SEC: u64 = 1000_000;
-
 impl Tracer {
     pub fn default() -> Self {
         Tracer {
             reporter: Rc::new(RemoteReporter::default()),
             codec: Codec::ZipkinB3TextMap,
+            baggage_restriction_manager: Rc::new(DefaultBaggageRestrictionManager::default()),
+            clock: Rc::new(SystemClock),
+            id_generator: Rc::new(RandomIdGenerator),
+            trace_id_128bit: env::var("JAEGER_TRACEID_128BIT")
+                .map(|value| is_enabled(&value))
+                .unwrap_or(false),
+            span_limits: SpanLimits::default(),
+            scope_manager: Rc::new(ThreadLocalScopeManager),
+            observers: Rc::new(Vec::new()),
+            processors: Rc::new(Vec::new()),
         }
     }
 
-    pub fn timestamp() -> u64 {
-        let duration = SystemTime::now().duration_since(UNIX_EPOCH);
-        let seconds = duration
-            .clone()
-            .map(|duration| duration.as_secs())
-            .unwrap_or(0);
+    /// Send finished spans to the given reporter instead of the Jaeger agent.
+    pub fn with_reporter<R>(mut self, reporter: R) -> Self
+    where
+        R: Reporter<'static, Span = Span> + 'static,
+    {
+        self.reporter = Rc::new(reporter);
+        self
+    }
+
+    /// Use the given manager to decide which baggage items spans may carry.
+    pub fn with_baggage_restriction_manager<M>(mut self, manager: M) -> Self
+    where
+        M: BaggageRestrictionManager + 'static,
+    {
+        self.baggage_restriction_manager = Rc::new(manager);
+        self
+    }
+
+    /// Use the given clock for span timestamps and durations.
+    pub fn with_clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        self.clock = Rc::new(clock);
+        self
+    }
+
+    /// Use the given generator for new trace and span ids.
+    pub fn with_id_generator<G>(mut self, id_generator: G) -> Self
+    where
+        G: IdGenerator + 'static,
+    {
+        self.id_generator = Rc::new(id_generator);
+        self
+    }
+
+    /// Generate 128-bit instead of 64-bit trace ids for new traces.
+    pub fn with_trace_id_128bit(mut self, trace_id_128bit: bool) -> Self {
+        self.trace_id_128bit = trace_id_128bit;
+        self
+    }
+
+    /// Cap the number of tags and logs, and the length of values, on each span.
+    pub fn with_span_limits(mut self, span_limits: SpanLimits) -> Self {
+        self.span_limits = span_limits;
+        self
+    }
+
+    /// Use the given manager to track the active span.
+    pub fn with_scope_manager<M>(mut self, manager: M) -> Self
+    where
+        M: ScopeManager<SpanContext> + 'static,
+    {
+        self.scope_manager = Rc::new(manager);
+        self
+    }
 
-        let nanoseconds = duration
-            .map(|duration| duration.subsec_nanos())
-            .unwrap_or(0);
+    /// Notify the observer of the lifecycle of every span. Observers are
+    /// called in the order they were added.
+    pub fn with_observer<O>(mut self, observer: O) -> Self
+    where
+        O: SpanObserver + 'static,
+    {
+        Rc::make_mut(&mut self.observers).push(Rc::new(observer));
+        self
+    }
 
-        (seconds * MICROS_PER_SEC) + (u64::from(nanoseconds) / NANOS_PER_MICRO)
+    /// Run the processor on every span before it is reported. Processors are
+    /// chained in the order they were added.
+    pub fn with_processor<P>(mut self, processor: P) -> Self
+    where
+        P: SpanProcessor + 'static,
+    {
+        Rc::make_mut(&mut self.processors).push(Rc::new(processor));
+        self
     }
+
+    /// The current wall-clock time in microseconds, according to the tracer's clock.
+    pub fn timestamp(&self) -> u64 {
+        self.clock.timestamp()
+    }
+
+    pub fn reporter(&self) -> &Rc<Reporter<'static, Span = Span>> {
+        &self.reporter
+    }
+
+    pub fn baggage_restriction_manager(&self) -> &Rc<BaggageRestrictionManager> {
+        &self.baggage_restriction_manager
+    }
+
+    pub fn clock(&self) -> &Rc<Clock> {
+        &self.clock
+    }
+
+    pub fn id_generator(&self) -> &Rc<IdGenerator> {
+        &self.id_generator
+    }
+
+    pub fn trace_id_128bit(&self) -> bool {
+        self.trace_id_128bit
+    }
+
+    pub fn span_limits(&self) -> SpanLimits {
+        self.span_limits
+    }
+
+    /// The scope manager as a shared handle. `OpentracingTracer::scope_manager`
+    /// lends it out as a plain reference instead.
+    pub fn shared_scope_manager(&self) -> &Rc<ScopeManager<SpanContext>> {
+        &self.scope_manager
+    }
+
+    pub fn observers(&self) -> &Rc<Vec<Rc<SpanObserver>>> {
+        &self.observers
+    }
+
+    pub fn processors(&self) -> &Rc<Vec<Rc<SpanProcessor>>> {
+        &self.processors
+    }
+
     pub fn report(&self, span: &Span) {
         self.reporter.report(span)
     }
@@ -56,34 +227,35 @@ impl Tracer {
 impl<'a> OpentracingTracer<'a> for Tracer {
     type SpanContext = SpanContext;
     type Span = Span;
-    type Carrier = HashMap<String, String>;
     type Error = Error;
 
-    fn start_span(
-        &self,
-        operation_name: String,
-        child_of: Option<&Self::SpanContext>,
-    ) -> Self::Span {
-        self.start_span_at(operation_name, child_of, Self::timestamp())
+    fn scope_manager(&self) -> Option<&ScopeManager<SpanContext>> {
+        Some(&*self.scope_manager)
     }
 
-    fn start_span_at(
+    fn start_span_with_options(
         &self,
         operation_name: String,
-        child_of: Option<&Self::SpanContext>,
-        start_time: u64,
+        mut options: StartSpanOptions<Self::SpanContext>,
     ) -> Self::Span {
-        let mut span = Span::child(child_of, start_time, &self.reporter);
-        span.operation_name = operation_name;
-        span
+        if options.references.is_empty() && !options.ignore_active_span {
+            if let Some(active) = self.active_span_context() {
+                options.references.push(SpanReference::ChildOf(active));
+            }
+        }
+
+        Span::with_operation_name(operation_name, options, self)
     }
 
-    fn inject(
+    fn inject<W>(
         &self,
         span_context: &Self::SpanContext,
         format: &str,
-        carrier: &mut Self::Carrier,
-    ) -> Result<(), Self::Error> {
+        carrier: &mut W,
+    ) -> Result<(), Self::Error>
+    where
+        W: TextMapWriter + ?Sized,
+    {
         match format {
             _ => {
                 Injector::inject(&self.codec, span_context, carrier);
@@ -92,13 +264,33 @@ impl<'a> OpentracingTracer<'a> for Tracer {
         }
     }
 
-    fn extract(
+    fn extract<R>(
         &self,
         format: &str,
-        carrier: &Self::Carrier,
-    ) -> Result<Self::SpanContext, Self::Error> {
+        carrier: &R,
+    ) -> Result<Option<Self::SpanContext>, Self::Error>
+    where
+        R: TextMapReader + ?Sized,
+    {
         match format {
-            _ => Extractor::extract(&self.codec, carrier).ok_or(Error::UnableToExtract),
+            _ => Extractor::extract(&self.codec, carrier).map_err(Error::from),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_enabled() {
+        assert!(is_enabled("1"));
+        assert!(is_enabled("true"));
+        assert!(is_enabled("TRUE"));
+        assert!(is_enabled("True"));
+        assert!(!is_enabled("0"));
+        assert!(!is_enabled("false"));
+        assert!(!is_enabled(""));
+        assert!(!is_enabled("yes"));
+    }
+}
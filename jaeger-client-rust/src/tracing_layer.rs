@@ -0,0 +1,475 @@
+use opentracing_rust_wip::{
+    Span as OpentracingSpan, SpanReference, StartSpanOptions, TagValue, Tracer as OpentracingTracer,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use tracing_core::field::{Field, Visit};
+use tracing_core::span::{Attributes, Id, Record};
+use tracing_core::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+use span::{Span, SpanContext};
+use thread_tracer::ThreadTracer;
+use tracer::Tracer;
+
+thread_local! {
+    /// Jaeger spans started on this thread, by layer and `tracing` span id.
+    static OPEN_SPANS: RefCell<HashMap<(usize, u64), Span>> = RefCell::new(HashMap::new());
+}
+
+/// A `tracing_subscriber::Layer` that reports `tracing` spans as Jaeger spans.
+///
+/// Span fields become tags and events become span logs. A `tracing` span is a
+/// child of its `tracing` parent, or else of the active opentracing span, and
+/// is itself the active opentracing span while entered, so both kinds of
+/// instrumentation end up in one trace.
+///
+/// Jaeger tracers can't be shared between threads, so the layer creates one
+/// per thread from the given factory to report spans with. The Jaeger span is
+/// started when the `tracing` span is created, so observers and `SpanLogger`s
+/// see it while it is open. A `tracing` span closed on another thread than it
+/// was created on can't reach that span, and is started again when it closes.
+///
+/// ```ignore
+/// let subscriber = FmtSubscriber::builder()
+///     .finish()
+///     .with(JaegerLayer::new());
+/// tracing::subscriber::set_global_default(subscriber)?;
+/// ```
+pub struct JaegerLayer {
+    tracer: ThreadTracer,
+    spans: Mutex<HashMap<u64, PendingSpan>>,
+}
+
+/// What is known about a `tracing` span until it closes.
+struct PendingSpan {
+    context: SpanContext,
+    operation_name: String,
+    start_time: u64,
+    references: Vec<SpanReference<SpanContext>>,
+    start_tags: Vec<(String, TagValue)>,
+    /// Tags recorded after the span was created.
+    tags: Vec<(String, TagValue)>,
+    logs: Vec<(u64, Vec<(String, TagValue)>)>,
+}
+
+impl PendingSpan {
+    fn start_options(&mut self) -> StartSpanOptions<SpanContext> {
+        StartSpanOptions {
+            references: self.references.clone(),
+            start_time: Some(self.start_time),
+            tags: self.start_tags.drain(..).collect(),
+            ..StartSpanOptions::default()
+        }
+    }
+}
+
+/// Collects `tracing` field values as tag values.
+#[derive(Default)]
+struct FieldVisitor {
+    fields: Vec<(String, TagValue)>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.push((field.name().to_owned(), TagValue::I64(value)));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.push((field.name().to_owned(), TagValue::U64(value)));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields
+            .push((field.name().to_owned(), TagValue::Boolean(value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields
+            .push((field.name().to_owned(), TagValue::String(value.to_owned())));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &fmt::Debug) {
+        self.fields.push((
+            field.name().to_owned(),
+            TagValue::String(format!("{:?}", value)),
+        ));
+    }
+}
+
+impl JaegerLayer {
+    /// Reports through a `Tracer::default()` on each thread.
+    pub fn new() -> Self {
+        Self::with_tracer_factory(Tracer::default)
+    }
+
+    /// Reports through tracers created by the factory, once per thread.
+    pub fn with_tracer_factory<F>(tracer_factory: F) -> Self
+    where
+        F: Fn() -> Tracer + Send + Sync + 'static,
+    {
+        JaegerLayer {
+            tracer: ThreadTracer::new(tracer_factory),
+            spans: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn span_context(&self, id: &Id) -> Option<SpanContext> {
+        self.spans
+            .lock()
+            .ok()
+            .and_then(|spans| spans.get(&id.into_u64()).map(|span| span.context.clone()))
+    }
+
+    /// Keeps the span started for the `tracing` span until it closes.
+    fn open(&self, id: &Id, span: Span) {
+        OPEN_SPANS.with(|open_spans| {
+            let mut open_spans = open_spans.borrow_mut();
+            // Spans closed on other threads leave theirs behind here. Clearing
+            // them out before the map would grow keeps it bounded by the
+            // number of open spans.
+            if open_spans.len() == open_spans.capacity() {
+                if let Ok(spans) = self.spans.lock() {
+                    open_spans.retain(|&(layer, id), span| {
+                        layer != self.tracer.id()
+                            || spans.get(&id).map_or(false, |pending| {
+                                pending.context.span_id() == span.context.span_id()
+                            })
+                    });
+                }
+            }
+            open_spans.insert((self.tracer.id(), id.into_u64()), span);
+        });
+    }
+
+    /// Takes the span started for the `tracing` span, if it was started on this thread.
+    fn take_open(&self, id: &Id, context: &SpanContext) -> Option<Span> {
+        OPEN_SPANS
+            .with(|open_spans| {
+                open_spans
+                    .borrow_mut()
+                    .remove(&(self.tracer.id(), id.into_u64()))
+            })
+            .filter(|span| span.context.span_id() == context.span_id())
+    }
+}
+
+impl Default for JaegerLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for JaegerLayer
+where
+    S: Subscriber,
+{
+    fn new_span(&self, attrs: &Attributes, id: &Id, ctx: Context<S>) {
+        let tracing_parent = if let Some(parent) = attrs.parent() {
+            self.span_context(parent)
+        } else if attrs.is_contextual() {
+            ctx.current_span()
+                .id()
+                .and_then(|parent| self.span_context(parent))
+        } else {
+            None
+        };
+
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+
+        let metadata = attrs.metadata();
+        let mut tags = vec![(
+            "target".to_owned(),
+            TagValue::String(metadata.target().to_owned()),
+        )];
+        tags.extend(visitor.fields);
+
+        let started = self.tracer.with(|tracer| {
+            let parent = match tracing_parent {
+                Some(parent) => Some(parent),
+                None if attrs.is_contextual() && attrs.parent().is_none() => {
+                    tracer.active_span_context()
+                }
+                None => None,
+            };
+
+            let context = SpanContext::child(
+                parent.as_ref(),
+                &**tracer.id_generator(),
+                tracer.trace_id_128bit(),
+            );
+            let mut pending = PendingSpan {
+                context: context.clone(),
+                operation_name: metadata.name().to_owned(),
+                start_time: tracer.timestamp(),
+                references: parent.into_iter().map(SpanReference::ChildOf).collect(),
+                start_tags: tags,
+                tags: Vec::new(),
+                logs: Vec::new(),
+            };
+            let options = pending.start_options();
+            let span = Span::with_context(context, pending.operation_name.clone(), options, tracer);
+            (pending, span)
+        });
+
+        if let Some((pending, span)) = started {
+            if let Ok(mut spans) = self.spans.lock() {
+                spans.insert(id.into_u64(), pending);
+            }
+            self.open(id, span);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record, _ctx: Context<S>) {
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+
+        if let Ok(mut spans) = self.spans.lock() {
+            if let Some(span) = spans.get_mut(&id.into_u64()) {
+                span.tags.extend(visitor.fields);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event, ctx: Context<S>) {
+        let parent = if let Some(parent) = event.parent() {
+            Some(parent.clone())
+        } else if event.is_contextual() {
+            ctx.current_span().id().cloned()
+        } else {
+            None
+        };
+
+        let parent = match parent {
+            Some(parent) => parent,
+            None => return,
+        };
+
+        let metadata = event.metadata();
+        let mut fields = vec![
+            ("event".to_owned(), TagValue::String("log".to_owned())),
+            (
+                "level".to_owned(),
+                TagValue::String(metadata.level().to_string()),
+            ),
+            (
+                "target".to_owned(),
+                TagValue::String(metadata.target().to_owned()),
+            ),
+        ];
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        fields.extend(visitor.fields);
+
+        let timestamp = match self.tracer.with(|tracer| tracer.timestamp()) {
+            Some(timestamp) => timestamp,
+            None => return,
+        };
+        if let Ok(mut spans) = self.spans.lock() {
+            if let Some(span) = spans.get_mut(&parent.into_u64()) {
+                span.logs.push((timestamp, fields));
+            }
+        }
+    }
+
+    fn on_enter(&self, id: &Id, _ctx: Context<S>) {
+        if let Some(context) = self.span_context(id) {
+            self.tracer.with(|tracer| {
+                if let Some(scope_manager) = tracer.scope_manager() {
+                    scope_manager.activate(context);
+                }
+            });
+        }
+    }
+
+    fn on_exit(&self, id: &Id, _ctx: Context<S>) {
+        if let Some(context) = self.span_context(id) {
+            self.tracer.with(|tracer| {
+                if let Some(scope_manager) = tracer.scope_manager() {
+                    scope_manager.deactivate(&context);
+                }
+            });
+        }
+    }
+
+    fn on_close(&self, id: Id, _ctx: Context<S>) {
+        let pending = match self.spans.lock() {
+            Ok(mut spans) => spans.remove(&id.into_u64()),
+            Err(_) => None,
+        };
+
+        let mut pending = match pending {
+            Some(pending) => pending,
+            None => return,
+        };
+
+        let open = self.take_open(&id, &pending.context);
+        self.tracer.with(|tracer| {
+            let mut span = match open {
+                Some(span) => span,
+                None => {
+                    let options = pending.start_options();
+                    Span::with_context(pending.context, pending.operation_name, options, tracer)
+                }
+            };
+
+            for (key, value) in pending.tags {
+                span.set_tag(key, value);
+            }
+            for (timestamp, fields) in pending.logs {
+                span.log_at(timestamp, fields);
+            }
+
+            span.finish();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::{Level, Log, Record as LogRecord};
+    use observer::SpanObserver;
+    use opentracing_rust_wip::{Reporter, ScopeManager};
+    use scope_manager::ThreadLocalScopeManager;
+    use span::SpanId;
+    use span_logger::SpanLogger;
+    use std::sync::Arc;
+    use tracing::Level as TracingLevel;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::FmtSubscriber;
+
+    /// The parts of a reported span that the tests look at.
+    struct ReportedSpan {
+        operation_name: String,
+        context: SpanContext,
+        tags: HashMap<String, TagValue>,
+        logs: Vec<HashMap<String, TagValue>>,
+    }
+
+    /// Records every span reported to it, from any thread.
+    #[derive(Clone, Default)]
+    struct CapturingReporter {
+        spans: Arc<Mutex<Vec<ReportedSpan>>>,
+    }
+
+    impl<'a> Reporter<'a> for CapturingReporter {
+        type Span = Span;
+
+        fn report(&self, span: &Span) {
+            if let Ok(mut spans) = self.spans.lock() {
+                spans.push(ReportedSpan {
+                    operation_name: span.operation_name.clone(),
+                    context: span.context.clone(),
+                    tags: span.tags.clone(),
+                    logs: span.logs.iter().map(|(_, fields)| fields.clone()).collect(),
+                });
+            }
+        }
+    }
+
+    /// Records the operation name and span id of every span as it starts.
+    #[derive(Clone, Default)]
+    struct StartedSpans(Arc<Mutex<Vec<(String, Option<SpanId>)>>>);
+
+    impl SpanObserver for StartedSpans {
+        fn on_start(&self, span: &Span) {
+            if let Ok(mut started) = self.0.lock() {
+                started.push((span.operation_name.clone(), span.context.span_id()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_tracing_span_is_active_parent() {
+        let tracer = Tracer::default();
+        let subscriber = FmtSubscriber::builder().finish().with(JaegerLayer::new());
+
+        ::tracing::subscriber::with_default(subscriber, || {
+            let outer = info_span!("outer", user_id = 42);
+            let _entered = outer.enter();
+
+            let span = tracer.start_span("opentracing".to_owned(), None);
+            assert!(span.context.parent_span_id().is_some());
+        });
+
+        assert!(ThreadLocalScopeManager.active().is_none());
+    }
+
+    #[test]
+    fn test_observers_see_tracing_span_context() {
+        let started = StartedSpans::default();
+        let observer = started.clone();
+        let layer = JaegerLayer::with_tracer_factory(move || {
+            Tracer::default().with_observer(observer.clone())
+        });
+        let subscriber = FmtSubscriber::builder().finish().with(layer);
+
+        let mut span_id = None;
+        ::tracing::subscriber::with_default(subscriber, || {
+            let span = info_span!("work");
+            span.in_scope(|| {
+                span_id = ThreadLocalScopeManager
+                    .active()
+                    .and_then(|context| context.span_id())
+            });
+        });
+
+        assert!(span_id.is_some());
+        let started = started.0.lock().expect("observer should not panic");
+        assert_eq!(*started, vec![("work".to_owned(), span_id)]);
+    }
+
+    #[test]
+    fn test_tracing_spans_are_reported() {
+        let reporter = CapturingReporter::default();
+        let spans = reporter.spans.clone();
+        let layer = JaegerLayer::with_tracer_factory(move || {
+            Tracer::default().with_reporter(reporter.clone())
+        });
+        let subscriber = FmtSubscriber::builder().finish().with(layer);
+
+        ::tracing::subscriber::with_default(subscriber, || {
+            let parent = info_span!("parent", user_id = 42);
+            let _entered = parent.enter();
+
+            event!(TracingLevel::INFO, items = 3, "loading cart");
+            SpanLogger::new().log(
+                &LogRecord::builder()
+                    .args(format_args!("logged"))
+                    .level(Level::Info)
+                    .target("test")
+                    .build(),
+            );
+            info_span!("child").in_scope(|| ());
+        });
+
+        let spans = spans.lock().expect("reporter should not panic");
+        assert_eq!(spans.len(), 2);
+        let (child, parent) = (&spans[0], &spans[1]);
+
+        assert_eq!(parent.operation_name, "parent");
+        assert!(
+            parent.tags["user_id"] == TagValue::I64(42)
+                || parent.tags["user_id"] == TagValue::U64(42)
+        );
+
+        assert_eq!(parent.logs.len(), 2);
+        assert_eq!(
+            parent.logs[0]["message"],
+            TagValue::String("loading cart".to_owned())
+        );
+        assert_eq!(parent.logs[0]["items"], TagValue::I64(3));
+        assert_eq!(
+            parent.logs[1]["message"],
+            TagValue::String("logged".to_owned())
+        );
+
+        assert_eq!(child.operation_name, "child");
+        assert_eq!(child.context.trace_id(), parent.context.trace_id());
+        assert_eq!(child.context.parent_span_id(), parent.context.span_id());
+    }
+}
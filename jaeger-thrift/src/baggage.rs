@@ -0,0 +1,369 @@
+// Autogenerated by Thrift Compiler (0.11.0)
+// DO NOT EDIT UNLESS YOU ARE SURE THAT YOU KNOW WHAT YOU ARE DOING
+
+#![allow(unused_imports)]
+#![allow(unused_extern_crates)]
+#![cfg_attr(feature = "cargo-clippy", allow(too_many_arguments, type_complexity))]
+#![cfg_attr(rustfmt, rustfmt_skip)]
+
+extern crate ordered_float;
+extern crate thrift;
+extern crate try_from;
+
+use ordered_float::OrderedFloat;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::From;
+use std::default::Default;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+use try_from::TryFrom;
+
+use thrift::{ApplicationError, ApplicationErrorKind, ProtocolError, ProtocolErrorKind, TThriftClient};
+use thrift::protocol::{TFieldIdentifier, TListIdentifier, TMapIdentifier, TMessageIdentifier, TMessageType, TInputProtocol, TOutputProtocol, TSetIdentifier, TStructIdentifier, TType};
+use thrift::protocol::field_id;
+use thrift::protocol::verify_expected_message_type;
+use thrift::protocol::verify_expected_sequence_number;
+use thrift::protocol::verify_expected_service_call;
+use thrift::protocol::verify_required_field_exists;
+use thrift::server::TProcessor;
+
+//
+// BaggageRestriction
+//
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct BaggageRestriction {
+  pub baggage_key: String,
+  pub max_value_length: i32,
+}
+
+impl BaggageRestriction {
+  pub fn new(baggage_key: String, max_value_length: i32) -> BaggageRestriction {
+    BaggageRestriction {
+      baggage_key: baggage_key,
+      max_value_length: max_value_length,
+    }
+  }
+  pub fn read_from_in_protocol(i_prot: &mut TInputProtocol) -> thrift::Result<BaggageRestriction> {
+    i_prot.read_struct_begin()?;
+    let mut f_1: Option<String> = None;
+    let mut f_2: Option<i32> = None;
+    loop {
+      let field_ident = i_prot.read_field_begin()?;
+      if field_ident.field_type == TType::Stop {
+        break;
+      }
+      let field_id = field_id(&field_ident)?;
+      match field_id {
+        1 => {
+          let val = i_prot.read_string()?;
+          f_1 = Some(val);
+        },
+        2 => {
+          let val = i_prot.read_i32()?;
+          f_2 = Some(val);
+        },
+        _ => {
+          i_prot.skip(field_ident.field_type)?;
+        },
+      };
+      i_prot.read_field_end()?;
+    }
+    i_prot.read_struct_end()?;
+    verify_required_field_exists("BaggageRestriction.baggage_key", &f_1)?;
+    verify_required_field_exists("BaggageRestriction.max_value_length", &f_2)?;
+    let ret = BaggageRestriction {
+      baggage_key: f_1.expect("auto-generated code should have checked for presence of required fields"),
+      max_value_length: f_2.expect("auto-generated code should have checked for presence of required fields"),
+    };
+    Ok(ret)
+  }
+  pub fn write_to_out_protocol(&self, o_prot: &mut TOutputProtocol) -> thrift::Result<()> {
+    let struct_ident = TStructIdentifier::new("BaggageRestriction");
+    o_prot.write_struct_begin(&struct_ident)?;
+    o_prot.write_field_begin(&TFieldIdentifier::new("baggageKey", TType::String, 1))?;
+    o_prot.write_string(&self.baggage_key)?;
+    o_prot.write_field_end()?;
+    o_prot.write_field_begin(&TFieldIdentifier::new("maxValueLength", TType::I32, 2))?;
+    o_prot.write_i32(self.max_value_length)?;
+    o_prot.write_field_end()?;
+    o_prot.write_field_stop()?;
+    o_prot.write_struct_end()
+  }
+}
+
+//
+// BaggageRestrictionManager service client
+//
+
+pub trait TBaggageRestrictionManagerSyncClient {
+  fn get_baggage_restrictions(&mut self, service_name: String) -> thrift::Result<Vec<BaggageRestriction>>;
+}
+
+pub trait TBaggageRestrictionManagerSyncClientMarker {}
+
+pub struct BaggageRestrictionManagerSyncClient<IP, OP> where IP: TInputProtocol, OP: TOutputProtocol {
+  _i_prot: IP,
+  _o_prot: OP,
+  _sequence_number: i32,
+}
+
+impl <IP, OP> BaggageRestrictionManagerSyncClient<IP, OP> where IP: TInputProtocol, OP: TOutputProtocol {
+  pub fn new(input_protocol: IP, output_protocol: OP) -> BaggageRestrictionManagerSyncClient<IP, OP> {
+    BaggageRestrictionManagerSyncClient { _i_prot: input_protocol, _o_prot: output_protocol, _sequence_number: 0 }
+  }
+}
+
+impl <IP, OP> TThriftClient for BaggageRestrictionManagerSyncClient<IP, OP> where IP: TInputProtocol, OP: TOutputProtocol {
+  fn i_prot_mut(&mut self) -> &mut TInputProtocol { &mut self._i_prot }
+  fn o_prot_mut(&mut self) -> &mut TOutputProtocol { &mut self._o_prot }
+  fn sequence_number(&self) -> i32 { self._sequence_number }
+  fn increment_sequence_number(&mut self) -> i32 { self._sequence_number += 1; self._sequence_number }
+}
+
+impl <IP, OP> TBaggageRestrictionManagerSyncClientMarker for BaggageRestrictionManagerSyncClient<IP, OP> where IP: TInputProtocol, OP: TOutputProtocol {}
+
+impl <C: TThriftClient + TBaggageRestrictionManagerSyncClientMarker> TBaggageRestrictionManagerSyncClient for C {
+  fn get_baggage_restrictions(&mut self, service_name: String) -> thrift::Result<Vec<BaggageRestriction>> {
+    (
+      {
+        self.increment_sequence_number();
+        let message_ident = TMessageIdentifier::new("getBaggageRestrictions", TMessageType::Call, self.sequence_number());
+        let call_args = GetBaggageRestrictionsArgs { service_name: service_name };
+        self.o_prot_mut().write_message_begin(&message_ident)?;
+        call_args.write_to_out_protocol(self.o_prot_mut())?;
+        self.o_prot_mut().write_message_end()?;
+        self.o_prot_mut().flush()
+      }
+    )?;
+    {
+      let message_ident = self.i_prot_mut().read_message_begin()?;
+      verify_expected_sequence_number(self.sequence_number(), message_ident.sequence_number)?;
+      verify_expected_service_call("getBaggageRestrictions", &message_ident.name)?;
+      if message_ident.message_type == TMessageType::Exception {
+        let remote_error = thrift::Error::read_application_error_from_in_protocol(self.i_prot_mut())?;
+        self.i_prot_mut().read_message_end()?;
+        return Err(thrift::Error::Application(remote_error))
+      }
+      verify_expected_message_type(TMessageType::Reply, message_ident.message_type)?;
+      let result = GetBaggageRestrictionsResult::read_from_in_protocol(self.i_prot_mut())?;
+      self.i_prot_mut().read_message_end()?;
+      result.ok_or()
+    }
+  }
+}
+
+//
+// BaggageRestrictionManager service processor
+//
+
+pub trait BaggageRestrictionManagerSyncHandler {
+  fn handle_get_baggage_restrictions(&self, service_name: String) -> thrift::Result<Vec<BaggageRestriction>>;
+}
+
+pub struct BaggageRestrictionManagerSyncProcessor<H: BaggageRestrictionManagerSyncHandler> {
+  handler: H,
+}
+
+impl <H: BaggageRestrictionManagerSyncHandler> BaggageRestrictionManagerSyncProcessor<H> {
+  pub fn new(handler: H) -> BaggageRestrictionManagerSyncProcessor<H> {
+    BaggageRestrictionManagerSyncProcessor {
+      handler: handler,
+    }
+  }
+  fn process_get_baggage_restrictions(&self, incoming_sequence_number: i32, i_prot: &mut TInputProtocol, o_prot: &mut TOutputProtocol) -> thrift::Result<()> {
+    TBaggageRestrictionManagerProcessFunctions::process_get_baggage_restrictions(&self.handler, incoming_sequence_number, i_prot, o_prot)
+  }
+}
+
+pub struct TBaggageRestrictionManagerProcessFunctions;
+
+impl TBaggageRestrictionManagerProcessFunctions {
+  pub fn process_get_baggage_restrictions<H: BaggageRestrictionManagerSyncHandler>(handler: &H, incoming_sequence_number: i32, i_prot: &mut TInputProtocol, o_prot: &mut TOutputProtocol) -> thrift::Result<()> {
+    let args = GetBaggageRestrictionsArgs::read_from_in_protocol(i_prot)?;
+    match handler.handle_get_baggage_restrictions(args.service_name) {
+      Ok(handler_return) => {
+        let message_ident = TMessageIdentifier::new("getBaggageRestrictions", TMessageType::Reply, incoming_sequence_number);
+        o_prot.write_message_begin(&message_ident)?;
+        let ret = GetBaggageRestrictionsResult { result_value: Some(handler_return) };
+        ret.write_to_out_protocol(o_prot)?;
+        o_prot.write_message_end()?;
+        o_prot.flush()
+      },
+      Err(e) => {
+        match e {
+          thrift::Error::Application(app_err) => {
+            let message_ident = TMessageIdentifier::new("getBaggageRestrictions", TMessageType::Exception, incoming_sequence_number);
+            o_prot.write_message_begin(&message_ident)?;
+            thrift::Error::write_application_error_to_out_protocol(&app_err, o_prot)?;
+            o_prot.write_message_end()?;
+            o_prot.flush()
+          },
+          _ => {
+            let ret_err = {
+              ApplicationError::new(
+                ApplicationErrorKind::Unknown,
+                e.description()
+              )
+            };
+            let message_ident = TMessageIdentifier::new("getBaggageRestrictions", TMessageType::Exception, incoming_sequence_number);
+            o_prot.write_message_begin(&message_ident)?;
+            thrift::Error::write_application_error_to_out_protocol(&ret_err, o_prot)?;
+            o_prot.write_message_end()?;
+            o_prot.flush()
+          },
+        }
+      },
+    }
+  }
+}
+
+impl <H: BaggageRestrictionManagerSyncHandler> TProcessor for BaggageRestrictionManagerSyncProcessor<H> {
+  fn process(&self, i_prot: &mut TInputProtocol, o_prot: &mut TOutputProtocol) -> thrift::Result<()> {
+    let message_ident = i_prot.read_message_begin()?;
+    let res = match &*message_ident.name {
+      "getBaggageRestrictions" => {
+        self.process_get_baggage_restrictions(message_ident.sequence_number, i_prot, o_prot)
+      },
+      method => {
+        Err(
+          thrift::Error::Application(
+            ApplicationError::new(
+              ApplicationErrorKind::UnknownMethod,
+              format!("unknown method {}", method)
+            )
+          )
+        )
+      },
+    };
+    thrift::server::handle_process_result(&message_ident, res, o_prot)
+  }
+}
+
+//
+// GetBaggageRestrictionsArgs
+//
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct GetBaggageRestrictionsArgs {
+  service_name: String,
+}
+
+impl GetBaggageRestrictionsArgs {
+  fn read_from_in_protocol(i_prot: &mut TInputProtocol) -> thrift::Result<GetBaggageRestrictionsArgs> {
+    i_prot.read_struct_begin()?;
+    let mut f_1: Option<String> = None;
+    loop {
+      let field_ident = i_prot.read_field_begin()?;
+      if field_ident.field_type == TType::Stop {
+        break;
+      }
+      let field_id = field_id(&field_ident)?;
+      match field_id {
+        1 => {
+          let val = i_prot.read_string()?;
+          f_1 = Some(val);
+        },
+        _ => {
+          i_prot.skip(field_ident.field_type)?;
+        },
+      };
+      i_prot.read_field_end()?;
+    }
+    i_prot.read_struct_end()?;
+    verify_required_field_exists("GetBaggageRestrictionsArgs.service_name", &f_1)?;
+    let ret = GetBaggageRestrictionsArgs {
+      service_name: f_1.expect("auto-generated code should have checked for presence of required fields"),
+    };
+    Ok(ret)
+  }
+  fn write_to_out_protocol(&self, o_prot: &mut TOutputProtocol) -> thrift::Result<()> {
+    let struct_ident = TStructIdentifier::new("getBaggageRestrictions_args");
+    o_prot.write_struct_begin(&struct_ident)?;
+    o_prot.write_field_begin(&TFieldIdentifier::new("serviceName", TType::String, 1))?;
+    o_prot.write_string(&self.service_name)?;
+    o_prot.write_field_end()?;
+    o_prot.write_field_stop()?;
+    o_prot.write_struct_end()
+  }
+}
+
+//
+// GetBaggageRestrictionsResult
+//
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct GetBaggageRestrictionsResult {
+  result_value: Option<Vec<BaggageRestriction>>,
+}
+
+impl GetBaggageRestrictionsResult {
+  fn read_from_in_protocol(i_prot: &mut TInputProtocol) -> thrift::Result<GetBaggageRestrictionsResult> {
+    i_prot.read_struct_begin()?;
+    let mut f_0: Option<Vec<BaggageRestriction>> = None;
+    loop {
+      let field_ident = i_prot.read_field_begin()?;
+      if field_ident.field_type == TType::Stop {
+        break;
+      }
+      let field_id = field_id(&field_ident)?;
+      match field_id {
+        0 => {
+          let list_ident = i_prot.read_list_begin()?;
+          let mut val: Vec<BaggageRestriction> = Vec::with_capacity(list_ident.size as usize);
+          for _ in 0..list_ident.size {
+            let list_elem_0 = BaggageRestriction::read_from_in_protocol(i_prot)?;
+            val.push(list_elem_0);
+          }
+          i_prot.read_list_end()?;
+          f_0 = Some(val);
+        },
+        _ => {
+          i_prot.skip(field_ident.field_type)?;
+        },
+      };
+      i_prot.read_field_end()?;
+    }
+    i_prot.read_struct_end()?;
+    let ret = GetBaggageRestrictionsResult {
+      result_value: f_0,
+    };
+    Ok(ret)
+  }
+  fn write_to_out_protocol(&self, o_prot: &mut TOutputProtocol) -> thrift::Result<()> {
+    let struct_ident = TStructIdentifier::new("GetBaggageRestrictionsResult");
+    o_prot.write_struct_begin(&struct_ident)?;
+    if let Some(ref fld_var) = self.result_value {
+      o_prot.write_field_begin(&TFieldIdentifier::new("result_value", TType::List, 0))?;
+      o_prot.write_list_begin(&TListIdentifier::new(TType::Struct, fld_var.len() as i32))?;
+      for e in fld_var {
+        e.write_to_out_protocol(o_prot)?;
+        o_prot.write_list_end()?;
+      }
+      o_prot.write_field_end()?;
+      ()
+    } else {
+      ()
+    }
+    o_prot.write_field_stop()?;
+    o_prot.write_struct_end()
+  }
+  fn ok_or(self) -> thrift::Result<Vec<BaggageRestriction>> {
+    if self.result_value.is_some() {
+      Ok(self.result_value.unwrap())
+    } else {
+      Err(
+        thrift::Error::Application(
+          ApplicationError::new(
+            ApplicationErrorKind::MissingResult,
+            "no result received for GetBaggageRestrictions"
+          )
+        )
+      )
+    }
+  }
+}
+
@@ -3,5 +3,6 @@ extern crate try_from;
 extern crate thrift;
 
 pub mod agent;
+pub mod baggage;
 pub mod jaeger;
 pub mod zipkincore;
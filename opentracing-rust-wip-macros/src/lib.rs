@@ -0,0 +1,230 @@
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+#[macro_use]
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenTree};
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::{
+    AttributeArgs, Expr, FnArg, Ident, ItemFn, Lit, LitStr, Meta, NestedMeta, Pat, ReturnType, Type,
+};
+
+/// Runs the function inside a span that is a child of the active span.
+///
+/// * `name = "..."` sets the operation name, which defaults to the function name.
+/// * `tracer = "..."` is the expression for the tracer to use. Without it the
+///   function needs an argument named `tracer`.
+/// * `tags(arg, ...)` records the given arguments as tags, using their `Debug` form.
+///
+/// If the function returns a `Result`, an `Err` is recorded with `log_error`, so
+/// its error type needs to implement `std::error::Error`.
+///
+/// Functions returning `impl Trait` run their body inline, so an `Err` they
+/// return early with `return` or `?` is not recorded, although the span still
+/// finishes. `async fn`s are rejected: the span would end when the future is
+/// created rather than when it completes, so instrument the future instead.
+///
+/// ```ignore
+/// #[traced(name = "load user", tags(user_id))]
+/// fn load_user(tracer: &Tracer, user_id: u64) -> Result<User, DatabaseError> {
+///     ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn traced(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let function = parse_macro_input!(item as ItemFn);
+
+    let options = match TracedOptions::parse(args, &function) {
+        Ok(options) => options,
+        Err(error) => return error.to_compile_error().into(),
+    };
+
+    expand(function, options).into()
+}
+
+struct TracedOptions {
+    name: LitStr,
+    tracer: Expr,
+    tags: Vec<Ident>,
+}
+
+impl TracedOptions {
+    fn parse(args: AttributeArgs, function: &ItemFn) -> syn::Result<Self> {
+        if let Some(ref asyncness) = function.asyncness {
+            return Err(syn::Error::new(
+                asyncness.span(),
+                "#[traced] does not support `async fn`, instrument the future instead",
+            ));
+        }
+
+        let mut tracer_given = false;
+        let mut options = TracedOptions {
+            name: LitStr::new(&function.ident.to_string(), function.ident.span()),
+            tracer: parse_quote!(tracer),
+            tags: Vec::new(),
+        };
+
+        for arg in args {
+            match arg {
+                NestedMeta::Meta(Meta::NameValue(ref name_value)) if name_value.ident == "name" => {
+                    options.name = string_literal(&name_value.lit)?;
+                }
+                NestedMeta::Meta(Meta::NameValue(ref name_value))
+                    if name_value.ident == "tracer" =>
+                {
+                    options.tracer = string_literal(&name_value.lit)?.parse()?;
+                    tracer_given = true;
+                }
+                NestedMeta::Meta(Meta::List(ref list)) if list.ident == "tags" => {
+                    for tag in list.nested.iter() {
+                        match *tag {
+                            NestedMeta::Meta(Meta::Word(ref ident)) => {
+                                options.tags.push(ident.clone())
+                            }
+                            ref other => {
+                                return Err(syn::Error::new(
+                                    other.span(),
+                                    "expected an argument name",
+                                ))
+                            }
+                        }
+                    }
+                }
+                other => {
+                    return Err(syn::Error::new(
+                        other.span(),
+                        "expected `name = \"...\"`, `tracer = \"...\"` or `tags(...)`",
+                    ))
+                }
+            }
+        }
+
+        if !tracer_given && !has_argument(function, "tracer") {
+            return Err(syn::Error::new(
+                function.ident.span(),
+                "#[traced] needs a `tracer` argument, or `tracer = \"...\"` to say where the tracer is",
+            ));
+        }
+
+        Ok(options)
+    }
+}
+
+fn has_argument(function: &ItemFn, name: &str) -> bool {
+    function.decl.inputs.iter().any(|input| match *input {
+        FnArg::Captured(ref captured) => match captured.pat {
+            Pat::Ident(ref pat) => pat.ident == name,
+            _ => false,
+        },
+        _ => false,
+    })
+}
+
+/// Whether the return type contains `impl Trait`, which closures can't be annotated with.
+fn returns_impl_trait(output: &ReturnType) -> bool {
+    fn contains_impl(tokens: proc_macro2::TokenStream) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ref ident) => ident == "impl",
+            TokenTree::Group(ref group) => contains_impl(group.stream()),
+            _ => false,
+        })
+    }
+
+    contains_impl(output.into_token_stream())
+}
+
+fn string_literal(lit: &Lit) -> syn::Result<LitStr> {
+    match *lit {
+        Lit::Str(ref string) => Ok(string.clone()),
+        ref other => Err(syn::Error::new(other.span(), "expected a string literal")),
+    }
+}
+
+fn returns_result(output: &ReturnType) -> bool {
+    match *output {
+        ReturnType::Type(_, ref ty) => match **ty {
+            Type::Path(ref path) => path
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.value().ident == "Result")
+                .unwrap_or(false),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    }
+}
+
+fn expand(function: ItemFn, options: TracedOptions) -> proc_macro2::TokenStream {
+    let ItemFn {
+        attrs,
+        vis,
+        constness,
+        unsafety,
+        asyncness,
+        abi,
+        ident,
+        decl,
+        block,
+    } = function;
+
+    let inputs = &decl.inputs;
+    let output = &decl.output;
+    let generics = &decl.generics;
+    let where_clause = &decl.generics.where_clause;
+
+    let name = &options.name;
+    let tracer = &options.tracer;
+    let tag_names = options
+        .tags
+        .iter()
+        .map(|tag| LitStr::new(&tag.to_string(), tag.span()));
+    let tag_values = options.tags.iter();
+
+    let scope = Ident::new("__traced_scope", Span::call_site());
+    let result = Ident::new("__traced_result", Span::call_site());
+
+    // The closure catches early returns so that their errors are recorded too,
+    // but can't be annotated with an `impl Trait` return type.
+    let body = if returns_impl_trait(output) {
+        quote!(#block)
+    } else {
+        quote!((|| #output #block)())
+    };
+
+    let record_error = if returns_result(output) {
+        quote! {
+            if let Err(ref error) = #result {
+                ::opentracing_rust_wip::Span::log_error(&mut *#scope, error);
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    quote! {
+        #(#attrs)*
+        #vis #constness #unsafety #asyncness #abi fn #ident #generics(#inputs) #output
+        #where_clause
+        {
+            #[allow(unused_mut)]
+            let mut #scope = {
+                use ::opentracing_rust_wip::Tracer as __TracedTracer;
+                (#tracer)
+                    .span_builder(#name)
+                    #(.with_tag(#tag_names, format!("{:?}", #tag_values)))*
+                    .start_active()
+            };
+
+            let #result = #body;
+            #record_error
+            #scope.finish();
+            #result
+        }
+    }
+}
@@ -0,0 +1,570 @@
+use opentracing_api::SpanContext;
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+use std::slice;
+
+use {FinishedSpan, ScopeManager, Span, SpanReference, StartSpanOptions, TagValue, Tracer};
+use {TextMapReader, TextMapWriter};
+
+/// A span context of any tracer, used by `DynTracer` and `DynSpan`.
+#[derive(Clone)]
+pub struct DynSpanContext {
+    inner: Rc<Any>,
+    baggage: Vec<(String, String)>,
+}
+
+/// Iterates over the baggage of a `DynSpanContext`.
+pub struct DynBaggageIter<'a> {
+    inner: slice::Iter<'a, (String, String)>,
+}
+
+/// A span of any tracer, started by a `DynTracer`.
+pub struct DynSpan {
+    inner: Box<ErasedSpan>,
+    context: DynSpanContext,
+}
+
+/// A tracer of any type behind one concrete type, so that code can emit spans
+/// without being generic over the tracer.
+///
+/// ```ignore
+/// let tracer = DynTracer::new(jaeger_client_rust::Tracer::default());
+/// let request_tracer: HttpRequestTracer<DynTracer> = HttpRequestTracer::new(tracer);
+/// ```
+#[derive(Clone)]
+pub struct DynTracer {
+    inner: Rc<ErasedTracer>,
+    scope_manager: DynScopeManager,
+}
+
+/// Forwards to the scope manager of the tracer inside a `DynTracer`.
+#[derive(Clone)]
+struct DynScopeManager {
+    inner: Rc<ErasedTracer>,
+}
+
+/// Describes why a `DynTracer` could not inject or extract a span context.
+#[derive(Debug)]
+pub enum DynError {
+    /// The span context was created by a different type of tracer.
+    ForeignSpanContext,
+    /// The tracer inside the `DynTracer` failed.
+    Tracer(Box<Error>),
+}
+
+impl Error for DynError {
+    fn description(&self) -> &str {
+        match *self {
+            DynError::ForeignSpanContext => "Span context from a different tracer",
+            DynError::Tracer(_) => "Tracer error",
+        }
+    }
+
+    fn source(&self) -> Option<&(Error + 'static)> {
+        match *self {
+            DynError::ForeignSpanContext => None,
+            DynError::Tracer(ref error) => Some(&**error),
+        }
+    }
+}
+
+impl fmt::Display for DynError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DynError::ForeignSpanContext => write!(f, "{}", self.description()),
+            DynError::Tracer(ref error) => write!(f, "{}: {}", self.description(), error),
+        }
+    }
+}
+
+impl DynSpanContext {
+    pub fn new<C>(context: C) -> Self
+    where
+        C: for<'b> SpanContext<'b> + 'static,
+    {
+        let baggage = context
+            .baggage_items()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        DynSpanContext {
+            inner: Rc::new(context),
+            baggage,
+        }
+    }
+
+    /// Returns the original span context, if it is of the given type.
+    pub fn downcast_ref<C: 'static>(&self) -> Option<&C> {
+        self.inner.downcast_ref()
+    }
+}
+
+impl fmt::Debug for DynSpanContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DynSpanContext")
+            .field("baggage", &self.baggage)
+            .finish()
+    }
+}
+
+impl<'a> Iterator for DynBaggageIter<'a> {
+    type Item = (&'a String, &'a String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|&(ref key, ref value)| (key, value))
+    }
+}
+
+impl<'a> SpanContext<'a> for DynSpanContext {
+    type Iter = DynBaggageIter<'a>;
+
+    fn baggage_items(&'a self) -> Self::Iter {
+        DynBaggageIter {
+            inner: self.baggage.iter(),
+        }
+    }
+}
+
+/// The object-safe subset of `Span` that `DynSpan` forwards to.
+trait ErasedSpan {
+    fn erased_context(&self) -> DynSpanContext;
+    fn erased_set_tag(&mut self, key: String, value: TagValue);
+    fn erased_unset_tag(&mut self, key: String);
+    fn erased_tag(&self, key: String) -> Option<&TagValue>;
+    fn erased_log(&mut self, timestamp: Option<u64>, fields: Vec<(String, TagValue)>);
+    fn erased_set_baggage_item(&mut self, key: String, value: String);
+    fn erased_unset_baggage_item(&mut self, key: String);
+    fn erased_baggage_item(&self, key: String) -> Option<&String>;
+    fn erased_set_operation_name(&mut self, name: String);
+    fn erased_operation_name(&self) -> &String;
+    fn erased_finish(self: Box<Self>, timestamp: Option<u64>);
+}
+
+impl<S> ErasedSpan for S
+where
+    S: Span<'static>,
+    S::Context: for<'b> SpanContext<'b> + Clone + 'static,
+{
+    fn erased_context(&self) -> DynSpanContext {
+        DynSpanContext::new(self.context().clone())
+    }
+
+    fn erased_set_tag(&mut self, key: String, value: TagValue) {
+        self.set_tag(key, value)
+    }
+
+    fn erased_unset_tag(&mut self, key: String) {
+        self.unset_tag(key)
+    }
+
+    fn erased_tag(&self, key: String) -> Option<&TagValue> {
+        self.tag(key)
+    }
+
+    fn erased_log(&mut self, timestamp: Option<u64>, fields: Vec<(String, TagValue)>) {
+        match timestamp {
+            Some(timestamp) => self.log_at(timestamp, fields),
+            None => self.log(fields),
+        }
+    }
+
+    fn erased_set_baggage_item(&mut self, key: String, value: String) {
+        self.set_baggage_item(key, value)
+    }
+
+    fn erased_unset_baggage_item(&mut self, key: String) {
+        self.unset_baggage_item(key)
+    }
+
+    fn erased_baggage_item(&self, key: String) -> Option<&String> {
+        self.baggage_item(key)
+    }
+
+    fn erased_set_operation_name(&mut self, name: String) {
+        self.set_operation_name(name)
+    }
+
+    fn erased_operation_name(&self) -> &String {
+        self.operation_name()
+    }
+
+    fn erased_finish(self: Box<Self>, timestamp: Option<u64>) {
+        match timestamp {
+            Some(timestamp) => (*self).finish_at(timestamp),
+            None => (*self).finish(),
+        };
+    }
+}
+
+impl DynSpan {
+    fn new<S>(span: S) -> Self
+    where
+        S: Span<'static> + 'static,
+        S::Context: for<'b> SpanContext<'b> + Clone + 'static,
+    {
+        let context = span.erased_context();
+        DynSpan {
+            inner: Box::new(span),
+            context,
+        }
+    }
+}
+
+impl Span<'static> for DynSpan {
+    type Context = DynSpanContext;
+
+    fn context(&self) -> &DynSpanContext {
+        &self.context
+    }
+
+    fn set_tag<S, V>(&mut self, key: S, value: V)
+    where
+        S: Into<String>,
+        V: Into<TagValue>,
+    {
+        self.inner.erased_set_tag(key.into(), value.into())
+    }
+
+    fn unset_tag<S>(&mut self, key: S)
+    where
+        S: Into<String>,
+    {
+        self.inner.erased_unset_tag(key.into())
+    }
+
+    fn tag<S>(&self, key: S) -> Option<&TagValue>
+    where
+        S: Into<String>,
+    {
+        self.inner.erased_tag(key.into())
+    }
+
+    fn log_event(&mut self, event: String) {
+        self.log(vec![("event", TagValue::String(event))])
+    }
+
+    fn log<S, I>(&mut self, tags: I)
+    where
+        S: Into<String>,
+        I: IntoIterator<Item = (S, TagValue)>,
+    {
+        let fields = tags
+            .into_iter()
+            .map(|(key, value)| (key.into(), value))
+            .collect();
+        self.inner.erased_log(None, fields)
+    }
+
+    fn log_at<S, I>(&mut self, timestamp: u64, tags: I)
+    where
+        S: Into<String>,
+        I: IntoIterator<Item = (S, TagValue)>,
+    {
+        let fields = tags
+            .into_iter()
+            .map(|(key, value)| (key.into(), value))
+            .collect();
+        self.inner.erased_log(Some(timestamp), fields)
+    }
+
+    fn set_baggage_item<S>(&mut self, key: S, value: String)
+    where
+        S: Into<String>,
+    {
+        self.inner.erased_set_baggage_item(key.into(), value);
+        self.context = self.inner.erased_context();
+    }
+
+    fn unset_baggage_item<S>(&mut self, key: S)
+    where
+        S: Into<String>,
+    {
+        self.inner.erased_unset_baggage_item(key.into());
+        self.context = self.inner.erased_context();
+    }
+
+    fn baggage_item<S>(&self, key: S) -> Option<&String>
+    where
+        S: Into<String>,
+    {
+        self.inner.erased_baggage_item(key.into())
+    }
+
+    fn set_operation_name<S>(&mut self, name: S)
+    where
+        S: Into<String>,
+    {
+        self.inner.erased_set_operation_name(name.into())
+    }
+
+    fn operation_name(&self) -> &String {
+        self.inner.erased_operation_name()
+    }
+
+    fn finish(self) -> FinishedSpan<DynSpanContext> {
+        self.inner.erased_finish(None);
+        FinishedSpan::new(self.context)
+    }
+
+    fn finish_at(self, timestamp: u64) -> FinishedSpan<DynSpanContext> {
+        self.inner.erased_finish(Some(timestamp));
+        FinishedSpan::new(self.context)
+    }
+}
+
+/// The object-safe subset of `Tracer` that `DynTracer` forwards to.
+trait ErasedTracer {
+    fn erased_start_span(
+        &self,
+        operation_name: String,
+        options: StartSpanOptions<DynSpanContext>,
+    ) -> DynSpan;
+
+    fn erased_inject(
+        &self,
+        span_context: &DynSpanContext,
+        format: &str,
+        carrier: &mut TextMapWriter,
+    ) -> Result<(), DynError>;
+
+    fn erased_extract(
+        &self,
+        format: &str,
+        carrier: &TextMapReader,
+    ) -> Result<Option<DynSpanContext>, DynError>;
+
+    fn erased_activate(&self, span_context: DynSpanContext);
+    fn erased_deactivate(&self, span_context: &DynSpanContext);
+    fn erased_active(&self) -> Option<DynSpanContext>;
+}
+
+impl<T> ErasedTracer for T
+where
+    T: Tracer<'static>,
+    T::SpanContext: for<'b> SpanContext<'b>,
+    T::Error: 'static,
+{
+    fn erased_start_span(
+        &self,
+        operation_name: String,
+        options: StartSpanOptions<DynSpanContext>,
+    ) -> DynSpan {
+        // References to contexts of other tracers can't be followed and are dropped.
+        let references = options
+            .references
+            .iter()
+            .flat_map(|reference| match *reference {
+                SpanReference::ChildOf(ref context) => context
+                    .downcast_ref::<T::SpanContext>()
+                    .map(|context| SpanReference::ChildOf(context.clone())),
+                SpanReference::FollowsFrom(ref context) => context
+                    .downcast_ref::<T::SpanContext>()
+                    .map(|context| SpanReference::FollowsFrom(context.clone())),
+            })
+            .collect();
+
+        let options = StartSpanOptions {
+            references,
+            start_time: options.start_time,
+            tags: options.tags,
+            ignore_active_span: options.ignore_active_span,
+        };
+
+        DynSpan::new(self.start_span_with_options(operation_name, options))
+    }
+
+    fn erased_inject(
+        &self,
+        span_context: &DynSpanContext,
+        format: &str,
+        carrier: &mut TextMapWriter,
+    ) -> Result<(), DynError> {
+        let span_context = span_context
+            .downcast_ref::<T::SpanContext>()
+            .ok_or(DynError::ForeignSpanContext)?;
+
+        self.inject(span_context, format, carrier)
+            .map_err(|error| DynError::Tracer(Box::new(error)))
+    }
+
+    fn erased_extract(
+        &self,
+        format: &str,
+        carrier: &TextMapReader,
+    ) -> Result<Option<DynSpanContext>, DynError> {
+        self.extract(format, carrier)
+            .map(|span_context| span_context.map(DynSpanContext::new))
+            .map_err(|error| DynError::Tracer(Box::new(error)))
+    }
+
+    fn erased_activate(&self, span_context: DynSpanContext) {
+        if let (Some(manager), Some(span_context)) = (
+            self.scope_manager(),
+            span_context.downcast_ref::<T::SpanContext>(),
+        ) {
+            manager.activate(span_context.clone());
+        }
+    }
+
+    fn erased_deactivate(&self, span_context: &DynSpanContext) {
+        if let (Some(manager), Some(span_context)) = (
+            self.scope_manager(),
+            span_context.downcast_ref::<T::SpanContext>(),
+        ) {
+            manager.deactivate(span_context);
+        }
+    }
+
+    fn erased_active(&self) -> Option<DynSpanContext> {
+        self.active_span_context().map(DynSpanContext::new)
+    }
+}
+
+impl ScopeManager<DynSpanContext> for DynScopeManager {
+    fn activate(&self, span_context: DynSpanContext) {
+        self.inner.erased_activate(span_context)
+    }
+
+    fn deactivate(&self, span_context: &DynSpanContext) {
+        self.inner.erased_deactivate(span_context)
+    }
+
+    fn active(&self) -> Option<DynSpanContext> {
+        self.inner.erased_active()
+    }
+}
+
+impl DynTracer {
+    pub fn new<T>(tracer: T) -> Self
+    where
+        T: Tracer<'static> + 'static,
+        T::SpanContext: for<'b> SpanContext<'b>,
+        T::Error: 'static,
+    {
+        let inner: Rc<ErasedTracer> = Rc::new(tracer);
+        DynTracer {
+            inner: inner.clone(),
+            scope_manager: DynScopeManager { inner },
+        }
+    }
+}
+
+impl Tracer<'static> for DynTracer {
+    type SpanContext = DynSpanContext;
+    type Span = DynSpan;
+    type Error = DynError;
+
+    fn scope_manager(&self) -> Option<&ScopeManager<DynSpanContext>> {
+        Some(&self.scope_manager)
+    }
+
+    fn start_span_with_options(
+        &self,
+        operation_name: String,
+        options: StartSpanOptions<DynSpanContext>,
+    ) -> DynSpan {
+        self.inner.erased_start_span(operation_name, options)
+    }
+
+    fn inject<W>(
+        &self,
+        span_context: &DynSpanContext,
+        format: &str,
+        carrier: &mut W,
+    ) -> Result<(), DynError>
+    where
+        W: TextMapWriter + ?Sized,
+    {
+        self.inner
+            .erased_inject(span_context, format, &mut ErasedWriter(carrier))
+    }
+
+    fn extract<R>(&self, format: &str, carrier: &R) -> Result<Option<DynSpanContext>, DynError>
+    where
+        R: TextMapReader + ?Sized,
+    {
+        self.inner.erased_extract(format, &ErasedReader(carrier))
+    }
+}
+
+/// Lets an unsized carrier be passed on as a trait object.
+struct ErasedWriter<'c, W: TextMapWriter + ?Sized + 'c>(&'c mut W);
+
+/// Lets an unsized carrier be passed on as a trait object.
+struct ErasedReader<'c, R: TextMapReader + ?Sized + 'c>(&'c R);
+
+impl<'c, W: TextMapWriter + ?Sized> TextMapWriter for ErasedWriter<'c, W> {
+    fn set(&mut self, key: &str, value: &str) {
+        self.0.set(key, value)
+    }
+}
+
+impl<'c, R: TextMapReader + ?Sized> TextMapReader for ErasedReader<'c, R> {
+    fn get_first(&self, key: &str) -> Option<&str> {
+        self.0.get_first(key)
+    }
+
+    fn get_all(&self, key: &str) -> Vec<&str> {
+        self.0.get_all(key)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use {global_tracer, set_global_tracer, MockSpanContext, MockTracer};
+
+    #[test]
+    fn test_dyn_tracer() {
+        let tracer = DynTracer::new(MockTracer::new());
+
+        let parent = tracer.start_active_span("parent");
+        let mut child = tracer.span_builder("child").with_tag("key", "value").start();
+        child.set_baggage_item("user", "42".to_owned());
+
+        assert_eq!(child.tag("key"), Some(&TagValue::from("value")));
+        assert_eq!(child.context().baggage_items().count(), 1);
+
+        let mock_parent = parent.context().downcast_ref::<MockSpanContext>().unwrap();
+        let mock_child = child.context().downcast_ref::<MockSpanContext>().unwrap();
+        assert_eq!(mock_child.trace_id, mock_parent.trace_id);
+
+        let mut carrier: HashMap<String, String> = HashMap::new();
+        tracer.inject(child.context(), "text_map", &mut carrier).unwrap();
+        let extracted = tracer.extract("text_map", &carrier).unwrap().unwrap();
+        assert_eq!(
+            extracted.downcast_ref::<MockSpanContext>().map(|context| context.span_id),
+            Some(mock_child.span_id)
+        );
+    }
+
+    #[test]
+    fn test_global_tracer() {
+        set_global_tracer(MockTracer::new);
+
+        let span = global_tracer().start_span("global".to_owned(), None);
+        assert!(span.context().downcast_ref::<MockSpanContext>().is_some());
+
+        let traced_setup = Arc::new(AtomicBool::new(true));
+        let setup = traced_setup.clone();
+        set_global_tracer(move || {
+            let span = global_tracer().start_span("setup".to_owned(), None);
+            let traced = span.context().downcast_ref::<MockSpanContext>().is_some();
+            setup.store(traced, Ordering::SeqCst);
+            MockTracer::new()
+        });
+
+        let span = global_tracer().start_span("global".to_owned(), None);
+        assert!(span.context().downcast_ref::<MockSpanContext>().is_some());
+        assert!(!traced_setup.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,79 @@
+use opentracing_api::SpanContext;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use {DynTracer, NoopTracer, Tracer};
+
+type TracerFactory = Arc<Fn() -> DynTracer + Send + Sync>;
+
+lazy_static! {
+    static ref GLOBAL_TRACER_FACTORY: RwLock<Option<TracerFactory>> = RwLock::new(None);
+}
+
+/// Bumped whenever the global tracer is replaced, so threads recreate theirs.
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// This thread's tracer, `None` while the factory is creating it.
+    static THREAD_TRACER: RefCell<Option<(usize, Option<DynTracer>)>> = RefCell::new(None);
+}
+
+/// Registers the process-wide tracer returned by `global_tracer()`.
+///
+/// Tracers don't have to be thread-safe, so rather than a tracer this takes a
+/// factory that is called once per thread to create that thread's tracer.
+///
+/// ```ignore
+/// set_global_tracer(jaeger_client_rust::Tracer::default);
+/// ```
+pub fn set_global_tracer<F, T>(factory: F)
+where
+    F: Fn() -> T + Send + Sync + 'static,
+    T: Tracer<'static> + 'static,
+    T::SpanContext: for<'b> SpanContext<'b>,
+    T::Error: 'static,
+{
+    let factory: TracerFactory = Arc::new(move || DynTracer::new(factory()));
+    if let Ok(mut global_factory) = GLOBAL_TRACER_FACTORY.write() {
+        *global_factory = Some(factory);
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Returns this thread's instance of the global tracer, or a `NoopTracer` if
+/// `set_global_tracer` hasn't been called.
+pub fn global_tracer() -> DynTracer {
+    let generation = GENERATION.load(Ordering::SeqCst);
+
+    let cached = THREAD_TRACER.with(|thread_tracer| match *thread_tracer.borrow() {
+        Some((tracer_generation, ref tracer)) if tracer_generation == generation => {
+            Some(tracer.clone())
+        }
+        _ => None,
+    });
+    match cached {
+        Some(Some(tracer)) => return tracer,
+        // Called from within the factory, which can't use the tracer it is
+        // still creating.
+        Some(None) => return DynTracer::new(NoopTracer),
+        None => {}
+    }
+
+    // The factory runs without the thread tracer borrowed, as creating a
+    // tracer may well trace or log through the global tracer itself.
+    THREAD_TRACER.with(|thread_tracer| *thread_tracer.borrow_mut() = Some((generation, None)));
+    let factory = GLOBAL_TRACER_FACTORY
+        .read()
+        .ok()
+        .and_then(|factory| factory.clone());
+    let tracer = match factory {
+        Some(factory) => factory(),
+        None => DynTracer::new(NoopTracer),
+    };
+
+    THREAD_TRACER.with(|thread_tracer| {
+        *thread_tracer.borrow_mut() = Some((generation, Some(tracer.clone())));
+    });
+    tracer
+}
@@ -1,11 +1,29 @@
+#[macro_use]
+extern crate lazy_static;
 extern crate opentracing_api;
 
+mod dynamic;
+mod global;
+mod mock;
+mod noop;
+mod reference;
 mod reporter;
+mod scope;
+mod semantic;
 mod span;
 mod tag;
+mod text_map;
 mod tracer;
 
+pub use dynamic::*;
+pub use global::*;
+pub use mock::*;
+pub use noop::*;
+pub use reference::*;
 pub use reporter::*;
+pub use scope::*;
+pub use semantic::*;
 pub use span::*;
 pub use tag::*;
+pub use text_map::*;
 pub use tracer::*;
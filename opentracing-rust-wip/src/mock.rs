@@ -0,0 +1,451 @@
+use opentracing_api::SpanContext;
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::Iter as HashMapIter;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use {FinishedSpan, ScopeManager, Span, SpanReference, StartSpanOptions, TagValue, Tracer};
+use {TextMapReader, TextMapWriter};
+
+const TRACE_ID_KEY: &str = "traceid";
+const SPAN_ID_KEY: &str = "spanid";
+const BAGGAGE_PREFIX: &str = "baggage-";
+
+/// A tracer that records the spans it starts, for testing instrumentation.
+///
+/// ```ignore
+/// let tracer = MockTracer::new();
+/// handle_request(&tracer);
+///
+/// let spans = tracer.finished_spans();
+/// assert_eq!(spans[0].operation_name, "handle_request");
+/// ```
+#[derive(Default)]
+pub struct MockTracer {
+    next_id: Cell<u64>,
+    started_spans: Rc<RefCell<Vec<MockSpanData>>>,
+    finished_spans: Rc<RefCell<Vec<MockSpanData>>>,
+    scope_manager: MockScopeManager,
+}
+
+/// The span context of a `MockSpan`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockSpanContext {
+    pub trace_id: u64,
+    pub span_id: u64,
+    pub baggage: HashMap<String, String>,
+}
+
+/// Everything recorded on a `MockSpan`.
+#[derive(Clone, Debug)]
+pub struct MockSpanData {
+    pub context: MockSpanContext,
+    pub operation_name: String,
+    pub references: Vec<SpanReference<MockSpanContext>>,
+    pub tags: HashMap<String, TagValue>,
+    pub logs: Vec<(u64, HashMap<String, TagValue>)>,
+    pub start_time: u64,
+    /// The end timestamp, once the span has finished.
+    pub finish_time: Option<u64>,
+}
+
+/// A span started by a `MockTracer`.
+pub struct MockSpan {
+    data: MockSpanData,
+    finished_spans: Rc<RefCell<Vec<MockSpanData>>>,
+}
+
+/// Tracks the active span context of a `MockTracer`.
+#[derive(Default)]
+pub struct MockScopeManager {
+    active: RefCell<Vec<MockSpanContext>>,
+}
+
+/// Describes why a span context could not be extracted from a carrier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MockError {
+    /// A required key was missing from the carrier.
+    MissingKey(&'static str),
+    /// The id stored under the key is not a decimal number.
+    MalformedId(&'static str),
+}
+
+impl Error for MockError {
+    fn description(&self) -> &str {
+        match *self {
+            MockError::MissingKey(_) => "Missing key",
+            MockError::MalformedId(_) => "Malformed id",
+        }
+    }
+}
+
+impl fmt::Display for MockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MockError::MissingKey(key) | MockError::MalformedId(key) => {
+                write!(f, "{}: {}", self.description(), key)
+            }
+        }
+    }
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| {
+            duration.as_secs() * 1_000_000 + u64::from(duration.subsec_nanos()) / 1_000
+        })
+        .unwrap_or(0)
+}
+
+impl MockTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The spans started so far, as they were when they started.
+    pub fn started_spans(&self) -> Vec<MockSpanData> {
+        self.started_spans.borrow().clone()
+    }
+
+    /// The spans finished so far, in the order they finished.
+    pub fn finished_spans(&self) -> Vec<MockSpanData> {
+        self.finished_spans.borrow().clone()
+    }
+
+    /// Forgets all recorded spans.
+    pub fn reset(&self) {
+        self.started_spans.borrow_mut().clear();
+        self.finished_spans.borrow_mut().clear();
+    }
+
+    fn next_id(&self) -> u64 {
+        let id = self.next_id.get() + 1;
+        self.next_id.set(id);
+        id
+    }
+}
+
+impl MockSpanData {
+    /// The span id of the parent, from the first `ChildOf` reference, or else
+    /// the first reference.
+    pub fn parent_id(&self) -> Option<u64> {
+        self.references
+            .iter()
+            .find(|reference| match reference {
+                SpanReference::ChildOf(_) => true,
+                SpanReference::FollowsFrom(_) => false,
+            })
+            .or_else(|| self.references.first())
+            .map(|reference| reference.context().span_id)
+    }
+}
+
+impl MockSpan {
+    /// Everything recorded on the span so far.
+    pub fn data(&self) -> &MockSpanData {
+        &self.data
+    }
+
+    fn record(mut self, finish_time: u64) -> FinishedSpan<MockSpanContext> {
+        self.data.finish_time = Some(finish_time);
+        self.finished_spans.borrow_mut().push(self.data.clone());
+        FinishedSpan::new(self.data.context)
+    }
+}
+
+impl<'a> SpanContext<'a> for MockSpanContext {
+    type Iter = HashMapIter<'a, String, String>;
+
+    fn baggage_items(&'a self) -> Self::Iter {
+        self.baggage.iter()
+    }
+}
+
+impl<'a> Span<'a> for MockSpan {
+    type Context = MockSpanContext;
+
+    fn context(&self) -> &MockSpanContext {
+        &self.data.context
+    }
+
+    fn set_tag<S, V>(&mut self, key: S, value: V)
+    where
+        S: Into<String>,
+        V: Into<TagValue>,
+    {
+        self.data.tags.insert(key.into(), value.into());
+    }
+
+    fn unset_tag<S>(&mut self, key: S)
+    where
+        S: Into<String>,
+    {
+        self.data.tags.remove(&key.into());
+    }
+
+    fn tag<S>(&self, key: S) -> Option<&TagValue>
+    where
+        S: Into<String>,
+    {
+        self.data.tags.get(&key.into())
+    }
+
+    fn log_event(&mut self, event: String) {
+        self.log(vec![("event", TagValue::String(event))])
+    }
+
+    fn log<S, I>(&mut self, tags: I)
+    where
+        S: Into<String>,
+        I: IntoIterator<Item = (S, TagValue)>,
+    {
+        self.log_at(timestamp(), tags)
+    }
+
+    fn log_at<S, I>(&mut self, timestamp: u64, tags: I)
+    where
+        S: Into<String>,
+        I: IntoIterator<Item = (S, TagValue)>,
+    {
+        self.data.logs.push((
+            timestamp,
+            tags.into_iter()
+                .map(|(key, value)| (key.into(), value))
+                .collect(),
+        ))
+    }
+
+    fn set_baggage_item<S>(&mut self, key: S, value: String)
+    where
+        S: Into<String>,
+    {
+        self.data.context.baggage.insert(key.into(), value);
+    }
+
+    fn unset_baggage_item<S>(&mut self, key: S)
+    where
+        S: Into<String>,
+    {
+        self.data.context.baggage.remove(&key.into());
+    }
+
+    fn baggage_item<S>(&self, key: S) -> Option<&String>
+    where
+        S: Into<String>,
+    {
+        self.data.context.baggage.get(&key.into())
+    }
+
+    fn set_operation_name<S>(&mut self, name: S)
+    where
+        S: Into<String>,
+    {
+        self.data.operation_name = name.into();
+    }
+
+    fn operation_name(&self) -> &String {
+        &self.data.operation_name
+    }
+
+    fn finish(self) -> FinishedSpan<MockSpanContext> {
+        self.record(timestamp())
+    }
+
+    fn finish_at(self, timestamp: u64) -> FinishedSpan<MockSpanContext> {
+        self.record(timestamp)
+    }
+}
+
+impl ScopeManager<MockSpanContext> for MockScopeManager {
+    fn activate(&self, context: MockSpanContext) {
+        self.active.borrow_mut().push(context);
+    }
+
+    fn deactivate(&self, context: &MockSpanContext) {
+        let mut active = self.active.borrow_mut();
+        let position = active.iter().rposition(|active| {
+            active.trace_id == context.trace_id && active.span_id == context.span_id
+        });
+        if let Some(position) = position {
+            active.remove(position);
+        }
+    }
+
+    fn active(&self) -> Option<MockSpanContext> {
+        self.active.borrow().last().cloned()
+    }
+}
+
+impl<'a> Tracer<'a> for MockTracer {
+    type SpanContext = MockSpanContext;
+    type Span = MockSpan;
+    type Error = MockError;
+
+    fn scope_manager(&self) -> Option<&ScopeManager<MockSpanContext>> {
+        Some(&self.scope_manager)
+    }
+
+    fn start_span_with_options(
+        &self,
+        operation_name: String,
+        mut options: StartSpanOptions<MockSpanContext>,
+    ) -> MockSpan {
+        if options.references.is_empty() && !options.ignore_active_span {
+            if let Some(active) = self.active_span_context() {
+                options.references.push(SpanReference::ChildOf(active));
+            }
+        }
+
+        let context = match options.parent() {
+            Some(parent) => MockSpanContext {
+                trace_id: parent.trace_id,
+                span_id: self.next_id(),
+                baggage: parent.baggage.clone(),
+            },
+            None => MockSpanContext {
+                trace_id: self.next_id(),
+                span_id: self.next_id(),
+                baggage: HashMap::new(),
+            },
+        };
+
+        let data = MockSpanData {
+            context,
+            operation_name,
+            references: options.references,
+            tags: options.tags.into_iter().collect(),
+            logs: Vec::new(),
+            start_time: options.start_time.unwrap_or_else(timestamp),
+            finish_time: None,
+        };
+
+        self.started_spans.borrow_mut().push(data.clone());
+
+        MockSpan {
+            data,
+            finished_spans: self.finished_spans.clone(),
+        }
+    }
+
+    fn inject<W>(
+        &self,
+        span_context: &MockSpanContext,
+        _format: &str,
+        carrier: &mut W,
+    ) -> Result<(), MockError>
+    where
+        W: TextMapWriter + ?Sized,
+    {
+        carrier.set(TRACE_ID_KEY, &span_context.trace_id.to_string());
+        carrier.set(SPAN_ID_KEY, &span_context.span_id.to_string());
+        for (key, value) in span_context.baggage.iter() {
+            carrier.set(&format!("{}{}", BAGGAGE_PREFIX, key), value);
+        }
+        Ok(())
+    }
+
+    /// Extracts the context from the `traceid` and `spanid` keys, and its baggage
+    /// from the keys starting with `baggage-`.
+    fn extract<R>(
+        &self,
+        _format: &str,
+        carrier: &R,
+    ) -> Result<Option<MockSpanContext>, MockError>
+    where
+        R: TextMapReader + ?Sized,
+    {
+        let parse_id = |key: &'static str| {
+            carrier
+                .get_first(key)
+                .ok_or(MockError::MissingKey(key))
+                .and_then(|value| value.parse().map_err(|_| MockError::MalformedId(key)))
+        };
+
+        match (carrier.get_first(TRACE_ID_KEY), carrier.get_first(SPAN_ID_KEY)) {
+            (None, None) => Ok(None),
+            _ => Ok(Some(MockSpanContext {
+                trace_id: parse_id(TRACE_ID_KEY)?,
+                span_id: parse_id(SPAN_ID_KEY)?,
+                baggage: extract_baggage(carrier),
+            })),
+        }
+    }
+}
+
+/// Collects the entries whose key starts with `baggage-`, in any case.
+fn extract_baggage<R>(carrier: &R) -> HashMap<String, String>
+where
+    R: TextMapReader + ?Sized,
+{
+    carrier
+        .keys()
+        .into_iter()
+        .filter_map(|key| {
+            let is_baggage = key
+                .get(..BAGGAGE_PREFIX.len())
+                .map_or(false, |prefix| prefix.eq_ignore_ascii_case(BAGGAGE_PREFIX));
+            if !is_baggage {
+                return None;
+            }
+
+            carrier
+                .get_first(key)
+                .map(|value| (key[BAGGAGE_PREFIX.len()..].to_owned(), value.to_owned()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_spans() {
+        let tracer = MockTracer::new();
+
+        let parent = tracer.start_active_span("parent");
+        let mut child = tracer.span_builder("child").with_tag("component", "db").start();
+        child.log_event("query".to_owned());
+        child.set_baggage_item("user", "42".to_owned());
+        child.finish();
+        parent.finish();
+
+        assert_eq!(tracer.started_spans().len(), 2);
+
+        let spans = tracer.finished_spans();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].operation_name, "child");
+        assert_eq!(spans[0].tags["component"], TagValue::from("db"));
+        assert_eq!(spans[0].logs.len(), 1);
+        assert_eq!(spans[0].context.baggage["user"], "42");
+        assert_eq!(spans[0].parent_id(), Some(spans[1].context.span_id));
+        assert_eq!(spans[0].context.trace_id, spans[1].context.trace_id);
+        assert!(spans[1].finish_time.is_some());
+    }
+
+    #[test]
+    fn test_inject_extract() {
+        let tracer = MockTracer::new();
+        let mut span = tracer.start_span("injected".to_owned(), None);
+        span.set_baggage_item("user", "42".to_owned());
+
+        let mut carrier: HashMap<String, String> = HashMap::new();
+        tracer.inject(span.context(), "text_map", &mut carrier).unwrap();
+
+        let extracted = tracer.extract("text_map", &carrier).unwrap().unwrap();
+        assert_eq!(extracted.trace_id, span.context().trace_id);
+        assert_eq!(extracted.span_id, span.context().span_id);
+        assert_eq!(extracted.baggage, span.context().baggage);
+        assert_eq!(extracted.baggage["user"], "42");
+
+        carrier.insert(SPAN_ID_KEY.to_owned(), "not a number".to_owned());
+        assert_eq!(
+            tracer.extract("text_map", &carrier),
+            Err(MockError::MalformedId(SPAN_ID_KEY))
+        );
+    }
+}
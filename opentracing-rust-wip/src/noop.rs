@@ -0,0 +1,204 @@
+use opentracing_api::SpanContext;
+use std::error::Error;
+use std::fmt;
+use std::iter::Empty;
+
+use {FinishedSpan, Reporter, Span, StartSpanOptions, TagValue, Tracer};
+use {TextMapReader, TextMapWriter};
+
+/// A tracer that records nothing, for when tracing is disabled or no tracer
+/// implementation is available.
+///
+/// ```ignore
+/// if env::var("JAEGER_DISABLED") == Ok("true".to_owned()) {
+///     app.wrap(HttpRequestTracer::new(NoopTracer))
+/// }
+/// ```
+#[derive(Default, Clone, Copy, Debug)]
+pub struct NoopTracer;
+
+/// The span context of a `NoopSpan`, which carries no ids or baggage.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NoopSpanContext;
+
+/// A span that discards everything recorded on it.
+#[derive(Default, Debug)]
+pub struct NoopSpan {
+    context: NoopSpanContext,
+    operation_name: String,
+}
+
+/// A reporter that drops every span.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct NoopReporter;
+
+/// The error type of `NoopTracer`, which never fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoopError {}
+
+impl Error for NoopError {
+    fn description(&self) -> &str {
+        match *self {}
+    }
+}
+
+impl fmt::Display for NoopError {
+    fn fmt(&self, _f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {}
+    }
+}
+
+impl<'a> SpanContext<'a> for NoopSpanContext {
+    type Iter = Empty<(&'a String, &'a String)>;
+
+    fn baggage_items(&'a self) -> Self::Iter {
+        ::std::iter::empty()
+    }
+}
+
+impl<'a> Span<'a> for NoopSpan {
+    type Context = NoopSpanContext;
+
+    fn context(&self) -> &NoopSpanContext {
+        &self.context
+    }
+
+    fn set_tag<S, V>(&mut self, _key: S, _value: V)
+    where
+        S: Into<String>,
+        V: Into<TagValue>,
+    {
+    }
+
+    fn unset_tag<S>(&mut self, _key: S)
+    where
+        S: Into<String>,
+    {
+    }
+
+    fn tag<S>(&self, _key: S) -> Option<&TagValue>
+    where
+        S: Into<String>,
+    {
+        None
+    }
+
+    fn log_event(&mut self, _event: String) {}
+
+    fn log<S, I>(&mut self, _tags: I)
+    where
+        S: Into<String>,
+        I: IntoIterator<Item = (S, TagValue)>,
+    {
+    }
+
+    fn log_at<S, I>(&mut self, _timestamp: u64, _tags: I)
+    where
+        S: Into<String>,
+        I: IntoIterator<Item = (S, TagValue)>,
+    {
+    }
+
+    fn set_baggage_item<S>(&mut self, _key: S, _value: String)
+    where
+        S: Into<String>,
+    {
+    }
+
+    fn unset_baggage_item<S>(&mut self, _key: S)
+    where
+        S: Into<String>,
+    {
+    }
+
+    fn baggage_item<S>(&self, _key: S) -> Option<&String>
+    where
+        S: Into<String>,
+    {
+        None
+    }
+
+    fn set_operation_name<S>(&mut self, _name: S)
+    where
+        S: Into<String>,
+    {
+    }
+
+    fn operation_name(&self) -> &String {
+        &self.operation_name
+    }
+
+    fn finish(self) -> FinishedSpan<NoopSpanContext> {
+        FinishedSpan::new(self.context)
+    }
+
+    fn finish_at(self, _timestamp: u64) -> FinishedSpan<NoopSpanContext> {
+        FinishedSpan::new(self.context)
+    }
+}
+
+impl<'a> Tracer<'a> for NoopTracer {
+    type SpanContext = NoopSpanContext;
+    type Span = NoopSpan;
+    type Error = NoopError;
+
+    fn start_span_with_options(
+        &self,
+        _operation_name: String,
+        _options: StartSpanOptions<NoopSpanContext>,
+    ) -> NoopSpan {
+        NoopSpan::default()
+    }
+
+    fn inject<W>(
+        &self,
+        _span_context: &NoopSpanContext,
+        _format: &str,
+        _carrier: &mut W,
+    ) -> Result<(), NoopError>
+    where
+        W: TextMapWriter + ?Sized,
+    {
+        Ok(())
+    }
+
+    fn extract<R>(
+        &self,
+        _format: &str,
+        _carrier: &R,
+    ) -> Result<Option<NoopSpanContext>, NoopError>
+    where
+        R: TextMapReader + ?Sized,
+    {
+        Ok(None)
+    }
+}
+
+impl<'a> Reporter<'a> for NoopReporter {
+    type Span = NoopSpan;
+
+    fn report(&self, _span: &NoopSpan) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_noop_tracer() {
+        let tracer = NoopTracer;
+        let mut span = tracer.span_builder("noop").with_tag("key", "value").start();
+        span.set_tag("other", 1);
+        span.set_baggage_item("key", "value".to_owned());
+        assert_eq!(span.tag("key"), None);
+        assert_eq!(span.baggage_item("key"), None);
+
+        let mut carrier: HashMap<String, String> = HashMap::new();
+        tracer.inject(span.context(), "headers", &mut carrier).unwrap();
+        assert!(carrier.is_empty());
+        assert_eq!(tracer.extract("headers", &carrier).unwrap(), None);
+
+        span.finish();
+    }
+}
@@ -0,0 +1,61 @@
+use TagValue;
+
+/// Describes the causal relationship between a span and one of its predecessors.
+///
+/// See also [References between Spans](https://github.com/opentracing/specification/blob/master/specification.md#references-between-spans)
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpanReference<C> {
+    /// The parent span depends on the result of the child span.
+    ChildOf(C),
+    /// The parent span does not depend on the result of the child span in any way.
+    FollowsFrom(C),
+}
+
+impl<C> SpanReference<C> {
+    /// Returns the span context being referenced.
+    pub fn context(&self) -> &C {
+        match *self {
+            SpanReference::ChildOf(ref context) => context,
+            SpanReference::FollowsFrom(ref context) => context,
+        }
+    }
+}
+
+/// Options used when starting a new span.
+#[derive(Clone, Debug)]
+pub struct StartSpanOptions<C> {
+    /// The spans this span is causally related to. The first `ChildOf` reference,
+    /// or else the first `FollowsFrom` reference, determines the trace and parent.
+    pub references: Vec<SpanReference<C>>,
+    /// Start timestamp in microseconds, or `None` to use the current time.
+    pub start_time: Option<u64>,
+    /// Tags set on the span before it starts, visible to samplers.
+    pub tags: Vec<(String, TagValue)>,
+    /// Do not implicitly use the active span as the parent when no references are given.
+    pub ignore_active_span: bool,
+}
+
+impl<C> Default for StartSpanOptions<C> {
+    fn default() -> Self {
+        StartSpanOptions {
+            references: Vec::new(),
+            start_time: None,
+            tags: Vec::new(),
+            ignore_active_span: false,
+        }
+    }
+}
+
+impl<C> StartSpanOptions<C> {
+    /// Returns the context of the span that should be used as the parent.
+    pub fn parent(&self) -> Option<&C> {
+        self.references
+            .iter()
+            .find(|reference| match reference {
+                SpanReference::ChildOf(_) => true,
+                SpanReference::FollowsFrom(_) => false,
+            })
+            .or_else(|| self.references.first())
+            .map(|reference| reference.context())
+    }
+}
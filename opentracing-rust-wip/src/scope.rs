@@ -0,0 +1,120 @@
+use std::ops::{Deref, DerefMut};
+
+use {FinishedSpan, Span, SpanGuard};
+
+/// Keeps track of the active span context, so that new spans can use it as
+/// their parent without it being passed down the call stack.
+///
+/// Activations nest: the active context is the most recently activated one
+/// that has not been deactivated yet.
+pub trait ScopeManager<C> {
+    /// Makes the context the active one.
+    fn activate(&self, context: C);
+
+    /// Removes the most recent activation of the context.
+    ///
+    /// Scopes may end out of order, so this must remove the given context
+    /// rather than whichever context was activated last.
+    fn deactivate(&self, context: &C);
+
+    /// Returns the active context, if any.
+    fn active(&self) -> Option<C>;
+}
+
+/// A span that is active for as long as the scope is alive.
+///
+/// Dropping the scope finishes the span and restores the previously active one.
+pub struct Scope<'m, 'a, S>
+where
+    S: Span<'a>,
+    S::Context: 'm,
+{
+    activation: Option<(&'m ScopeManager<S::Context>, S::Context)>,
+    span: Option<SpanGuard<'a, S>>,
+}
+
+impl<'m, 'a, S> Scope<'m, 'a, S>
+where
+    S: Span<'a>,
+    S::Context: Clone + 'm,
+{
+    /// Activates the span's context in the manager, if there is one.
+    pub fn new(manager: Option<&'m ScopeManager<S::Context>>, span: S) -> Self {
+        let activation = manager.map(|manager| {
+            let context = span.context().clone();
+            manager.activate(context.clone());
+            (manager, context)
+        });
+
+        Scope {
+            activation,
+            span: Some(SpanGuard::new(span)),
+        }
+    }
+
+    /// Deactivates and finishes the span now instead of when the scope is dropped.
+    pub fn finish(mut self) -> FinishedSpan<S::Context> {
+        let span = self.span.take().expect("scope is only emptied once");
+        drop(self);
+        span.finish()
+    }
+}
+
+impl<'m, 'a, S> Deref for Scope<'m, 'a, S>
+where
+    S: Span<'a>,
+    S::Context: 'm,
+{
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        &**self.span.as_ref().expect("scope is only emptied once")
+    }
+}
+
+impl<'m, 'a, S> DerefMut for Scope<'m, 'a, S>
+where
+    S: Span<'a>,
+    S::Context: 'm,
+{
+    fn deref_mut(&mut self) -> &mut S {
+        &mut **self.span.as_mut().expect("scope is only emptied once")
+    }
+}
+
+impl<'m, 'a, S> Drop for Scope<'m, 'a, S>
+where
+    S: Span<'a>,
+    S::Context: 'm,
+{
+    fn drop(&mut self) {
+        if let Some((manager, context)) = self.activation.take() {
+            manager.deactivate(&context);
+        }
+        // The span guard finishes the span when it is dropped after this.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {MockTracer, Tracer};
+
+    #[test]
+    fn test_out_of_order_drop() {
+        let tracer = MockTracer::new();
+
+        let outer = tracer.start_active_span("outer");
+        let inner = tracer.start_active_span("inner");
+        let inner_span_id = inner.context().span_id;
+
+        drop(outer);
+        assert_eq!(
+            tracer.active_span_context().map(|context| context.span_id),
+            Some(inner_span_id)
+        );
+
+        drop(inner);
+        assert!(tracer.active_span_context().is_none());
+    }
+}
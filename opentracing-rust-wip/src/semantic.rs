@@ -0,0 +1,148 @@
+use std::net::{IpAddr, SocketAddr};
+
+use {Span, Tags};
+
+/// The role of a span in an RPC or messaging interaction, recorded as `span.kind`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum SpanKind {
+    /// The client side of an RPC.
+    Client,
+    /// The server side of an RPC.
+    Server,
+    /// The producer of a message sent to a message bus.
+    Producer,
+    /// The consumer of a message received from a message bus.
+    Consumer,
+}
+
+impl SpanKind {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            SpanKind::Client => Tags::SpanKindClient.as_str(),
+            SpanKind::Server => Tags::SpanKindServer.as_str(),
+            SpanKind::Producer => Tags::SpanKindProducer.as_str(),
+            SpanKind::Consumer => Tags::SpanKindConsumer.as_str(),
+        }
+    }
+}
+
+/// Typed setters for the standard tags, so each is written under the right key
+/// with the right type.
+///
+/// Implemented for every `Span`.
+///
+/// See also [Semantic Conventions](https://github.com/opentracing/specification/blob/master/semantic_conventions.md)
+pub trait SemanticConventions<'a>: Span<'a> {
+    fn set_span_kind(&mut self, kind: SpanKind) {
+        self.set_tag(Tags::SpanKind.as_str(), kind.as_str());
+    }
+
+    fn set_component(&mut self, component: &str) {
+        self.set_tag(Tags::Component.as_str(), component);
+    }
+
+    fn set_error(&mut self, error: bool) {
+        self.set_tag(Tags::Error.as_str(), error);
+    }
+
+    fn set_sampling_priority(&mut self, priority: u32) {
+        self.set_tag(Tags::SamplingPriority.as_str(), priority);
+    }
+
+    fn set_http_method(&mut self, method: &str) {
+        self.set_tag(Tags::HttpMethod.as_str(), method);
+    }
+
+    fn set_http_url(&mut self, url: &str) {
+        self.set_tag(Tags::HttpUrl.as_str(), url);
+    }
+
+    fn set_http_route(&mut self, route: &str) {
+        self.set_tag(Tags::HttpRoute.as_str(), route);
+    }
+
+    fn set_http_status(&mut self, status: u16) {
+        self.set_tag(Tags::HttpStatus.as_str(), status);
+    }
+
+    fn set_peer_service(&mut self, service: &str) {
+        self.set_tag(Tags::PeerService.as_str(), service);
+    }
+
+    fn set_peer_hostname(&mut self, hostname: &str) {
+        self.set_tag(Tags::PeerHostname.as_str(), hostname);
+    }
+
+    fn set_peer_port(&mut self, port: u16) {
+        self.set_tag(Tags::PeerPort.as_str(), port);
+    }
+
+    /// Records the address under `peer.ipv4` or `peer.ipv6`.
+    fn set_peer_ip(&mut self, ip: IpAddr) {
+        let key = match ip {
+            IpAddr::V4(_) => Tags::PeerHostIpv4,
+            IpAddr::V6(_) => Tags::PeerHostIpv6,
+        };
+        self.set_tag(key.as_str(), ip.to_string());
+    }
+
+    /// Records the address under `peer.address`, and its parts under
+    /// `peer.ipv4` or `peer.ipv6` and `peer.port`.
+    fn set_peer_addr(&mut self, addr: SocketAddr) {
+        self.set_tag(Tags::PeerAddress.as_str(), addr.to_string());
+        self.set_peer_ip(addr.ip());
+        self.set_peer_port(addr.port());
+    }
+
+    /// Records an address that isn't a socket address, such as a
+    /// "host:port" string or a socket path.
+    fn set_peer_address(&mut self, address: &str) {
+        self.set_tag(Tags::PeerAddress.as_str(), address);
+    }
+
+    fn set_db_type(&mut self, db_type: &str) {
+        self.set_tag(Tags::DbType.as_str(), db_type);
+    }
+
+    fn set_db_instance(&mut self, instance: &str) {
+        self.set_tag(Tags::DbInstance.as_str(), instance);
+    }
+
+    fn set_db_user(&mut self, user: &str) {
+        self.set_tag(Tags::DbUser.as_str(), user);
+    }
+
+    fn set_db_statement(&mut self, statement: &str) {
+        self.set_tag(Tags::DbStatement.as_str(), statement);
+    }
+
+    fn set_message_bus_destination(&mut self, destination: &str) {
+        self.set_tag(Tags::MessageBusDestination.as_str(), destination);
+    }
+}
+
+impl<'a, S> SemanticConventions<'a> for S where S: Span<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {MockTracer, TagValue, Tracer};
+
+    #[test]
+    fn test_typed_setters() {
+        let tracer = MockTracer::new();
+        let mut span = tracer.start_span("request".to_owned(), None);
+
+        span.set_span_kind(SpanKind::Server);
+        span.set_http_status(404);
+        span.set_http_route("/users/{id}");
+        span.set_peer_addr("[::1]:8080".parse().unwrap());
+
+        assert_eq!(span.tag("span.kind"), Some(&TagValue::from("server")));
+        assert_eq!(span.tag("http.status_code"), Some(&TagValue::U16(404)));
+        assert_eq!(span.tag("http.route"), Some(&TagValue::from("/users/{id}")));
+        assert_eq!(span.tag("peer.address"), Some(&TagValue::from("[::1]:8080")));
+        assert_eq!(span.tag("peer.ipv6"), Some(&TagValue::from("::1")));
+        assert_eq!(span.tag("peer.port"), Some(&TagValue::U16(8080)));
+    }
+}
@@ -1,5 +1,9 @@
 use opentracing_api::SpanContext;
-use TagValue;
+use std::any::type_name;
+use std::error::Error;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use {TagValue, Tags};
 
 /// The `Span` represents the OpenTracing specification's Span contract.
 pub trait Span<'a> {
@@ -12,9 +16,10 @@ pub trait Span<'a> {
     fn context(&self) -> &Self::Context;
 
     /// Sets a key:value tag on the `Span`.
-    fn set_tag<S>(&mut self, key: S, value: TagValue)
+    fn set_tag<S, V>(&mut self, key: S, value: V)
     where
-        S: Into<String>;
+        S: Into<String>,
+        V: Into<TagValue>;
 
     /// Allows to unset a tag based on the given key. Noop if
     /// it doesn't exist.
@@ -42,6 +47,32 @@ pub trait Span<'a> {
         S: Into<String>,
         I: IntoIterator<Item = (S, TagValue)>;
 
+    /// Record an error following the OpenTracing conventions and set the `error` tag.
+    ///
+    /// The log carries `event=error`, `error.kind` (the type name of the error),
+    /// `error.object`, `message` and, if the error has sources, `error.causes`.
+    /// Trait objects such as `&Error` are accepted too, in which case the kind
+    /// is the type name their `Debug` form starts with.
+    fn log_error<E>(&mut self, error: &E)
+    where
+        E: Error + ?Sized,
+    {
+        let fields = error_fields(error);
+        self.set_tag(Tags::Error.as_str(), true);
+        self.log(fields);
+    }
+
+    /// Like `log_error`, additionally recording a backtrace under `stack`.
+    fn log_error_with_stack<E>(&mut self, error: &E, stack: String)
+    where
+        E: Error + ?Sized,
+    {
+        let mut fields = error_fields(error);
+        fields.push(("stack", TagValue::String(stack)));
+        self.set_tag(Tags::Error.as_str(), true);
+        self.log(fields);
+    }
+
     /// Sets a baggage item in the Span (and its SpanContext) as a key/value pair.
     fn set_baggage_item<S>(&mut self, key: S, value: String)
     where
@@ -68,10 +99,130 @@ pub trait Span<'a> {
     fn operation_name(&self) -> &String;
 
     /// Sets the end timestamp to now and finishes (records) the span.
-    fn finish(&self) -> FinishedSpan<Self::Context>;
+    ///
+    /// Finishing consumes the span, so it is recorded exactly once.
+    fn finish(self) -> FinishedSpan<Self::Context>;
 
     /// Sets an explicit end timestamp and finishes (records) the span.
-    fn finish_at(&self, timestamp: u64) -> FinishedSpan<Self::Context>;
+    fn finish_at(self, timestamp: u64) -> FinishedSpan<Self::Context>;
+}
+
+/// Finishes the wrapped span when dropped, so early returns and `?` can't leak it.
+pub struct SpanGuard<'a, S>
+where
+    S: Span<'a>,
+{
+    span: Option<S>,
+    _lifetime: PhantomData<&'a ()>,
+}
+
+impl<'a, S> SpanGuard<'a, S>
+where
+    S: Span<'a>,
+{
+    pub fn new(span: S) -> Self {
+        SpanGuard {
+            span: Some(span),
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Finishes the span now instead of when the guard is dropped.
+    pub fn finish(mut self) -> FinishedSpan<S::Context> {
+        self.take().finish()
+    }
+
+    /// Finishes the span with an explicit end timestamp.
+    pub fn finish_at(mut self, timestamp: u64) -> FinishedSpan<S::Context> {
+        self.take().finish_at(timestamp)
+    }
+
+    /// Returns the span without finishing it.
+    pub fn into_inner(mut self) -> S {
+        self.take()
+    }
+
+    fn take(&mut self) -> S {
+        self.span.take().expect("span guard is only emptied once")
+    }
+}
+
+impl<'a, S> Deref for SpanGuard<'a, S>
+where
+    S: Span<'a>,
+{
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        self.span.as_ref().expect("span guard is only emptied once")
+    }
+}
+
+impl<'a, S> DerefMut for SpanGuard<'a, S>
+where
+    S: Span<'a>,
+{
+    fn deref_mut(&mut self) -> &mut S {
+        self.span.as_mut().expect("span guard is only emptied once")
+    }
+}
+
+impl<'a, S> Drop for SpanGuard<'a, S>
+where
+    S: Span<'a>,
+{
+    fn drop(&mut self) {
+        if let Some(span) = self.span.take() {
+            span.finish();
+        }
+    }
+}
+
+/// The type name of the error. Trait objects only have `dyn Error` as their
+/// type name, so for them it is read from the start of the `Debug` form, such
+/// as `ParseIntError` in `ParseIntError { kind: InvalidDigit }`.
+fn error_kind<E>(error: &E) -> String
+where
+    E: Error + ?Sized,
+{
+    let type_name = type_name::<E>();
+    if !type_name.starts_with("dyn ") {
+        return type_name.to_owned();
+    }
+
+    let kind: String = format!("{:?}", error)
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == ':')
+        .collect();
+    if kind.is_empty() {
+        type_name.to_owned()
+    } else {
+        kind
+    }
+}
+
+fn error_fields<E>(error: &E) -> Vec<(&'static str, TagValue)>
+where
+    E: Error + ?Sized,
+{
+    let mut fields = vec![
+        ("event", TagValue::String("error".to_owned())),
+        ("error.kind", TagValue::String(error_kind(error))),
+        ("error.object", TagValue::String(format!("{:?}", error))),
+        ("message", TagValue::String(error.to_string())),
+    ];
+
+    let mut causes = Vec::new();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        causes.push(cause.to_string());
+        source = cause.source();
+    }
+    if !causes.is_empty() {
+        fields.push(("error.causes", TagValue::String(causes.join("\n"))));
+    }
+
+    fields
 }
 
 pub struct FinishedSpan<C> {
@@ -90,3 +241,122 @@ where
         &self.context
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt;
+    use std::num::ParseIntError;
+    use {MockTracer, Tracer};
+
+    #[derive(Debug)]
+    struct ConfigError {
+        source: ParseIntError,
+    }
+
+    impl fmt::Display for ConfigError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "invalid port")
+        }
+    }
+
+    impl Error for ConfigError {
+        fn source(&self) -> Option<&(Error + 'static)> {
+            Some(&self.source)
+        }
+    }
+
+    #[test]
+    fn test_log_error() {
+        let tracer = MockTracer::new();
+        let mut span = tracer.start_span("failing".to_owned(), None);
+
+        let source = "port".parse::<u16>().unwrap_err();
+        span.log_error(&ConfigError { source });
+        span.log_error_with_stack(&"x".parse::<u8>().unwrap_err(), "main.rs:1".to_owned());
+
+        let data = span.data();
+        assert_eq!(data.tags["error"], TagValue::Boolean(true));
+
+        let fields = &data.logs[0].1;
+        assert_eq!(fields["event"], TagValue::from("error"));
+        assert_eq!(
+            fields["error.kind"],
+            TagValue::from(type_name::<ConfigError>())
+        );
+        assert_eq!(fields["message"], TagValue::from("invalid port"));
+        assert_eq!(
+            fields["error.causes"],
+            TagValue::from("invalid digit found in string")
+        );
+        assert!(!data.logs[1].1.contains_key("error.causes"));
+        assert_eq!(
+            data.logs[1].1["error.kind"],
+            TagValue::from(type_name::<ParseIntError>())
+        );
+        assert_eq!(data.logs[1].1["stack"], TagValue::from("main.rs:1"));
+    }
+
+    #[test]
+    fn test_log_error_trait_object() {
+        let tracer = MockTracer::new();
+        let mut span = tracer.start_span("failing".to_owned(), None);
+
+        let error = "x".parse::<u8>().unwrap_err();
+        span.log_error(&error as &Error);
+        let boxed: Box<Error> = Box::new(ConfigError { source: error });
+        span.log_error(&*boxed);
+
+        let data = span.data();
+        assert_eq!(
+            data.logs[0].1["error.kind"],
+            TagValue::from("ParseIntError")
+        );
+        assert_eq!(
+            data.logs[0].1["message"],
+            TagValue::from("invalid digit found in string")
+        );
+        assert_eq!(data.logs[1].1["error.kind"], TagValue::from("ConfigError"));
+        assert_eq!(
+            data.logs[1].1["error.causes"],
+            TagValue::from("invalid digit found in string")
+        );
+    }
+
+    fn guarded_parse(tracer: &MockTracer, value: &str) -> Result<u8, ParseIntError> {
+        let mut span = SpanGuard::new(tracer.start_span("parse".to_owned(), None));
+        let parsed = value.parse::<u8>()?;
+        span.set_tag("value", parsed);
+        Ok(parsed)
+    }
+
+    #[test]
+    fn test_span_guard_finishes_once() {
+        let tracer = MockTracer::new();
+
+        let guard = SpanGuard::new(tracer.start_span("dropped".to_owned(), None));
+        drop(guard);
+        assert_eq!(tracer.finished_spans().len(), 1);
+
+        let guard = SpanGuard::new(tracer.start_span("finished".to_owned(), None));
+        guard.finish();
+        let finished = tracer.finished_spans();
+        assert_eq!(finished.len(), 2);
+        assert_eq!(finished[0].operation_name, "dropped");
+        assert_eq!(finished[1].operation_name, "finished");
+        assert!(finished.iter().all(|span| span.finish_time.is_some()));
+    }
+
+    #[test]
+    fn test_span_guard_finishes_on_early_return() {
+        let tracer = MockTracer::new();
+
+        assert!(guarded_parse(&tracer, "x").is_err());
+        assert_eq!(guarded_parse(&tracer, "7"), Ok(7));
+
+        let finished = tracer.finished_spans();
+        assert_eq!(finished.len(), 2);
+        assert!(!finished[0].tags.contains_key("value"));
+        assert_eq!(finished[1].tags["value"], TagValue::U8(7));
+    }
+}
@@ -9,11 +9,13 @@ const TAG_SPAN_KIND_CONSUMER: &str = "consumer";
 const TAG_HTTP_URL: &str = "http.url";
 const TAG_HTTP_STATUS_CODE: &str = "http.status_code";
 const TAG_HTTP_METHOD: &str = "http.method";
+const TAG_HTTP_ROUTE: &str = "http.route";
 const TAG_PEER_IPV4: &str = "peer.ipv4";
 const TAG_PEER_IPV6: &str = "peer.ipv6";
 const TAG_PEER_SERVICE: &str = "peer.service";
 const TAG_PEER_HOSTNAME: &str = "peer.hostname";
 const TAG_PEER_PORT: &str = "peer.port";
+const TAG_PEER_ADDRESS: &str = "peer.address";
 const TAG_SAMPLING_PRIORITY: &str = "sampling.priority";
 const TAG_SPAN_KIND: &str = "span.kind";
 const TAG_COMPONENT: &str = "component";
@@ -29,6 +31,9 @@ const TAG_MSGBUS_DEST: &str = "message_bus.destination";
 /// standardized data points. Tag names follow a general structure of namespacing.
 ///
 /// See also [Semantic Conventions](https://github.com/opentracing/specification/blob/master/semantic_conventions.md)
+///
+/// The `SpanKind*` variants are values of the `span.kind` tag rather than tag keys.
+/// Prefer the typed setters of `SemanticConventions`, such as `set_span_kind`.
 #[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
 pub enum Tags {
     /// A constant for setting the span kind to indicate that it represents a client span.
@@ -47,6 +52,8 @@ pub enum Tags {
     HttpStatus,
     /// HTTP_METHOD records the http method. Case-insensitive.
     HttpMethod,
+    /// HTTP_ROUTE records the matched route template, e.g. "/users/{id}".
+    HttpRoute,
     /// PEER_HOST_IPV4 records IPv4 host address of the peer.
     PeerHostIpv4,
     /// PEER_HOST_IPV6 records the IPv6 host address of the peer.
@@ -57,6 +64,9 @@ pub enum Tags {
     PeerHostname,
     /// PEER_PORT records the port number of the peer.
     PeerPort,
+    /// PEER_ADDRESS records the address of the peer, e.g. "host:port" or a
+    /// socket path.
+    PeerAddress,
     /// SAMPLING_PRIORITY determines the priority of sampling this Span.
     SamplingPriority,
     /// SPAN_KIND hints at the relationship between spans, e.g. client/server.
@@ -91,11 +101,13 @@ impl Tags {
             Tags::HttpUrl => TAG_HTTP_URL,
             Tags::HttpStatus => TAG_HTTP_STATUS_CODE,
             Tags::HttpMethod => TAG_HTTP_METHOD,
+            Tags::HttpRoute => TAG_HTTP_ROUTE,
             Tags::PeerHostIpv4 => TAG_PEER_IPV4,
             Tags::PeerHostIpv6 => TAG_PEER_IPV6,
             Tags::PeerService => TAG_PEER_SERVICE,
             Tags::PeerHostname => TAG_PEER_HOSTNAME,
             Tags::PeerPort => TAG_PEER_PORT,
+            Tags::PeerAddress => TAG_PEER_ADDRESS,
             Tags::SamplingPriority => TAG_SAMPLING_PRIORITY,
             Tags::SpanKind => TAG_SPAN_KIND,
             Tags::Component => TAG_COMPONENT,
@@ -127,11 +139,13 @@ impl FromStr for Tags {
             TAG_HTTP_URL => Ok(Tags::HttpUrl),
             TAG_HTTP_STATUS_CODE => Ok(Tags::HttpStatus),
             TAG_HTTP_METHOD => Ok(Tags::HttpMethod),
+            TAG_HTTP_ROUTE => Ok(Tags::HttpRoute),
             TAG_PEER_IPV4 => Ok(Tags::PeerHostIpv4),
             TAG_PEER_IPV6 => Ok(Tags::PeerHostIpv6),
             TAG_PEER_SERVICE => Ok(Tags::PeerService),
             TAG_PEER_HOSTNAME => Ok(Tags::PeerHostname),
             TAG_PEER_PORT => Ok(Tags::PeerPort),
+            TAG_PEER_ADDRESS => Ok(Tags::PeerAddress),
             TAG_SAMPLING_PRIORITY => Ok(Tags::SamplingPriority),
             TAG_SPAN_KIND => Ok(Tags::SpanKind),
             TAG_COMPONENT => Ok(Tags::Component),
@@ -171,7 +185,8 @@ impl fmt::Display for ParseTagsError {
     }
 }
 
-/// Tags Values per spec can be Strings, Booleans or Numerics.
+/// Tags Values per spec can be Strings, Booleans or Numerics. Binary values are
+/// supported by some tracers, such as Jaeger.
 ///
 /// Note that isize and usize are not included here since they
 /// are only meant to be used as pointers-sized types only.
@@ -189,6 +204,41 @@ pub enum TagValue {
     U64(u64),
     F32(f32),
     F64(f64),
+    Binary(Vec<u8>),
+}
+
+macro_rules! tag_value_from {
+    ($($source:ty => $variant:ident),*) => {
+        $(
+            impl From<$source> for TagValue {
+                fn from(value: $source) -> Self {
+                    TagValue::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+tag_value_from!(
+    String => String,
+    bool => Boolean,
+    i8 => I8,
+    i16 => I16,
+    i32 => I32,
+    i64 => I64,
+    u8 => U8,
+    u16 => U16,
+    u32 => U32,
+    u64 => U64,
+    f32 => F32,
+    f64 => F64,
+    Vec<u8> => Binary
+);
+
+impl<'a> From<&'a str> for TagValue {
+    fn from(value: &'a str) -> Self {
+        TagValue::String(value.to_owned())
+    }
 }
 
 #[cfg(test)]
@@ -206,6 +256,15 @@ mod tests {
         assert_eq!(String::from("client"), Tags::SpanKindClient.to_string());
     }
 
+    #[test]
+    fn test_tag_value_from() {
+        assert_eq!(TagValue::I32(42), 42.into());
+        assert_eq!(TagValue::U64(u64::max_value()), u64::max_value().into());
+        assert_eq!(TagValue::String("value".to_owned()), "value".into());
+        assert_eq!(TagValue::Boolean(true), true.into());
+        assert_eq!(TagValue::Binary(vec![1, 2]), vec![1u8, 2].into());
+    }
+
     #[test]
     fn test_tag_from_string() {
         assert_eq!(Ok(Tags::Error), Tags::from_str("error"));
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+/// A carrier that span contexts can be extracted from, such as a set of HTTP headers.
+///
+/// Keys are compared case-insensitively.
+pub trait TextMapReader {
+    /// Returns the first value stored under the key.
+    fn get_first(&self, key: &str) -> Option<&str>;
+
+    /// Returns every value stored under the key, in the order they were received.
+    fn get_all(&self, key: &str) -> Vec<&str>;
+
+    /// Returns every key, once each and as stored, so that prefixed entries
+    /// such as baggage can be found.
+    fn keys(&self) -> Vec<&str>;
+}
+
+/// A carrier that span contexts can be injected into, such as a set of HTTP headers.
+pub trait TextMapWriter {
+    /// Sets the value for the key, replacing any values already stored under it.
+    fn set(&mut self, key: &str, value: &str);
+}
+
+impl TextMapReader for HashMap<String, String> {
+    fn get_first(&self, key: &str) -> Option<&str> {
+        match HashMap::get(self, key) {
+            Some(value) => Some(value.as_str()),
+            None => self
+                .iter()
+                .find(|(name, _value)| name.eq_ignore_ascii_case(key))
+                .map(|(_name, value)| value.as_str()),
+        }
+    }
+
+    fn get_all(&self, key: &str) -> Vec<&str> {
+        self.iter()
+            .filter(|(name, _value)| name.eq_ignore_ascii_case(key))
+            .map(|(_name, value)| value.as_str())
+            .collect()
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        HashMap::keys(self).map(|name| name.as_str()).collect()
+    }
+}
+
+impl TextMapWriter for HashMap<String, String> {
+    fn set(&mut self, key: &str, value: &str) {
+        self.retain(|name, _value| !name.eq_ignore_ascii_case(key));
+        self.insert(key.to_owned(), value.to_owned());
+    }
+}
+
+impl TextMapReader for HashMap<String, Vec<String>> {
+    fn get_first(&self, key: &str) -> Option<&str> {
+        self.iter()
+            .filter(|(name, _values)| name.eq_ignore_ascii_case(key))
+            .flat_map(|(_name, values)| values.first())
+            .map(|value| value.as_str())
+            .next()
+    }
+
+    fn get_all(&self, key: &str) -> Vec<&str> {
+        self.iter()
+            .filter(|(name, _values)| name.eq_ignore_ascii_case(key))
+            .flat_map(|(_name, values)| values.iter().map(|value| value.as_str()))
+            .collect()
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        HashMap::keys(self).map(|name| name.as_str()).collect()
+    }
+}
+
+impl TextMapWriter for HashMap<String, Vec<String>> {
+    fn set(&mut self, key: &str, value: &str) {
+        self.retain(|name, _values| !name.eq_ignore_ascii_case(key));
+        self.insert(key.to_owned(), vec![value.to_owned()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_case_insensitive_lookup() {
+        let mut carrier: HashMap<String, String> = HashMap::new();
+        carrier.insert("X-B3-TraceId".to_owned(), "1".to_owned());
+
+        assert_eq!(Some("1"), carrier.get_first("x-b3-traceid"));
+        assert_eq!(vec!["1"], carrier.get_all("X-B3-TRACEID"));
+        assert_eq!(vec!["X-B3-TraceId"], TextMapReader::keys(&carrier));
+
+        carrier.set("x-b3-traceid", "2");
+        assert_eq!(1, carrier.len());
+        assert_eq!(Some("2"), carrier.get_first("X-B3-TraceId"));
+    }
+
+    #[test]
+    fn test_repeated_values() {
+        let mut carrier: HashMap<String, Vec<String>> = HashMap::new();
+        carrier.insert(
+            "x-request-id".to_owned(),
+            vec!["first".to_owned(), "second".to_owned()],
+        );
+
+        assert_eq!(Some("first"), carrier.get_first("X-Request-Id"));
+        assert_eq!(vec!["first", "second"], carrier.get_all("x-request-id"));
+    }
+}
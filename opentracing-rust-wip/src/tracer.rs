@@ -1,38 +1,234 @@
 use opentracing_api::*;
+use std::marker::PhantomData;
 
-use Span;
+use {Scope, ScopeManager, Span};
+use {SpanReference, StartSpanOptions, TagValue};
+use {TextMapReader, TextMapWriter};
 
 /// A tracer that can start spans and inject/extract span contexts.
 pub trait Tracer<'a> {
-    type SpanContext: SpanContext<'a> + 'static;
+    type SpanContext: SpanContext<'a> + Clone + 'static;
     type Span: Span<'a, Context = Self::SpanContext> + 'static;
-    type Carrier;
-    type Error;
+    type Error: ::std::error::Error;
 
     /// Start a new span with the current time stamp.
     fn start_span(
         &self,
         operation_name: String,
         child_of: Option<&Self::SpanContext>,
-    ) -> Self::Span;
+    ) -> Self::Span {
+        self.start_span_with_options(operation_name, child_of_options(child_of, None))
+    }
 
     fn start_span_at(
         &self,
         operation_name: String,
         child_of: Option<&Self::SpanContext>,
         start_time: u64,
+    ) -> Self::Span {
+        self.start_span_with_options(operation_name, child_of_options(child_of, Some(start_time)))
+    }
+
+    /// Returns a builder for a new span.
+    fn span_builder<'t, S>(&'t self, operation_name: S) -> SpanBuilder<'t, 'a, Self>
+    where
+        Self: Sized,
+        S: Into<String>,
+    {
+        SpanBuilder::new(self, operation_name)
+    }
+
+    /// Start a new span as a child of the active span, and make it the active span.
+    fn start_active_span<'t, S>(&'t self, operation_name: S) -> Scope<'t, 'a, Self::Span>
+    where
+        Self: Sized,
+        S: Into<String>,
+    {
+        SpanBuilder::new(self, operation_name).start_active()
+    }
+
+    /// The scope manager tracking the active span, if the tracer has one.
+    fn scope_manager(&self) -> Option<&ScopeManager<Self::SpanContext>> {
+        None
+    }
+
+    /// Returns the context of the active span, if any.
+    fn active_span_context(&self) -> Option<Self::SpanContext> {
+        self.scope_manager().and_then(|manager| manager.active())
+    }
+
+    /// Start a new span with any number of references to other spans.
+    ///
+    /// Unless `ignore_active_span` is set, a span without references should
+    /// be a child of the active span.
+    fn start_span_with_options(
+        &self,
+        operation_name: String,
+        options: StartSpanOptions<Self::SpanContext>,
     ) -> Self::Span;
 
-    fn inject(
+    /// Inject the span context into the carrier.
+    fn inject<W>(
         &self,
         span_context: &Self::SpanContext,
         format: &str,
-        carrier: &mut Self::Carrier,
-    ) -> Result<(), Self::Error>;
+        carrier: &mut W,
+    ) -> Result<(), Self::Error>
+    where
+        W: TextMapWriter + ?Sized;
 
-    fn extract(
+    /// Extract a span context from the carrier.
+    ///
+    /// Returns `Ok(None)` if the carrier does not contain a span context, and an
+    /// error if it contains one that is incomplete or corrupted.
+    fn extract<R>(
         &self,
         format: &str,
-        carrier: &Self::Carrier,
-    ) -> Result<Self::SpanContext, Self::Error>;
+        carrier: &R,
+    ) -> Result<Option<Self::SpanContext>, Self::Error>
+    where
+        R: TextMapReader + ?Sized;
+}
+
+fn child_of_options<C: Clone>(child_of: Option<&C>, start_time: Option<u64>) -> StartSpanOptions<C> {
+    StartSpanOptions {
+        references: child_of
+            .map(|context| vec![SpanReference::ChildOf(context.clone())])
+            .unwrap_or_default(),
+        start_time,
+        ..StartSpanOptions::default()
+    }
+}
+
+/// Collects the options for a new span before starting it.
+///
+/// ```ignore
+/// let span = tracer
+///     .span_builder("op")
+///     .child_of(parent.context())
+///     .with_tag("component", "db")
+///     .start();
+/// ```
+pub struct SpanBuilder<'t, 'a, T>
+where
+    T: Tracer<'a> + 't,
+{
+    tracer: &'t T,
+    operation_name: String,
+    options: StartSpanOptions<T::SpanContext>,
+    _lifetime: PhantomData<&'a ()>,
+}
+
+impl<'t, 'a, T> SpanBuilder<'t, 'a, T>
+where
+    T: Tracer<'a> + 't,
+{
+    pub fn new<S>(tracer: &'t T, operation_name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        SpanBuilder {
+            tracer,
+            operation_name: operation_name.into(),
+            options: StartSpanOptions::default(),
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Adds a `ChildOf` reference to the given span context.
+    pub fn child_of(mut self, context: &T::SpanContext) -> Self {
+        self.options
+            .references
+            .push(SpanReference::ChildOf(context.clone()));
+        self
+    }
+
+    /// Adds a `FollowsFrom` reference to the given span context.
+    pub fn follows_from(mut self, context: &T::SpanContext) -> Self {
+        self.options
+            .references
+            .push(SpanReference::FollowsFrom(context.clone()));
+        self
+    }
+
+    /// Sets a tag before the span starts.
+    pub fn with_tag<S, V>(mut self, key: S, value: V) -> Self
+    where
+        S: Into<String>,
+        V: Into<TagValue>,
+    {
+        self.options.tags.push((key.into(), value.into()));
+        self
+    }
+
+    /// Sets an explicit start timestamp in microseconds.
+    pub fn with_start_time(mut self, start_time: u64) -> Self {
+        self.options.start_time = Some(start_time);
+        self
+    }
+
+    /// Do not use the active span as an implicit parent.
+    pub fn ignore_active_span(mut self) -> Self {
+        self.options.ignore_active_span = true;
+        self
+    }
+
+    /// Starts the span.
+    pub fn start(self) -> T::Span {
+        self.tracer
+            .start_span_with_options(self.operation_name, self.options)
+    }
+
+    /// Starts the span and makes it the active span until the scope is dropped.
+    pub fn start_active(self) -> Scope<'t, 'a, T::Span> {
+        let tracer = self.tracer;
+        Scope::new(tracer.scope_manager(), self.start())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use MockTracer;
+
+    #[test]
+    fn test_span_builder_options() {
+        let tracer = MockTracer::new();
+        let parent = tracer.start_span("parent".to_owned(), None);
+        let previous = tracer.start_span("previous".to_owned(), None);
+
+        let span = tracer
+            .span_builder("child")
+            .child_of(parent.context())
+            .follows_from(previous.context())
+            .with_tag("component", "db")
+            .with_start_time(42)
+            .start();
+
+        let data = span.data();
+        assert_eq!(data.operation_name, "child");
+        assert_eq!(
+            data.references,
+            vec![
+                SpanReference::ChildOf(parent.context().clone()),
+                SpanReference::FollowsFrom(previous.context().clone()),
+            ]
+        );
+        assert_eq!(data.parent_id(), Some(parent.context().span_id));
+        assert_eq!(data.start_time, 42);
+        assert_eq!(data.tags["component"], TagValue::from("db"));
+    }
+
+    #[test]
+    fn test_span_builder_ignore_active_span() {
+        let tracer = MockTracer::new();
+        let active = tracer.start_active_span("active");
+
+        let implicit = tracer.span_builder("implicit").start();
+        assert_eq!(implicit.data().parent_id(), Some(active.context().span_id));
+
+        let detached = tracer.span_builder("detached").ignore_active_span().start();
+        assert_eq!(detached.data().parent_id(), None);
+        assert_ne!(detached.context().trace_id, active.context().trace_id);
+    }
 }